@@ -1,16 +1,34 @@
-use std::str::FromStr;
+use std::{collections::HashSet, fs, str::FromStr};
 
+use allowlist_plugin::AllowlistEntry;
 use anchor_client::{
-    solana_client::rpc_client::RpcClient,
+    solana_client::{
+        rpc_client::RpcClient,
+        rpc_config::RpcProgramAccountsConfig,
+        rpc_filter::{Memcmp, RpcFilterType},
+    },
     solana_sdk::{
-        commitment_config::CommitmentConfig, signature::read_keypair_file, signer::Signer,
+        commitment_config::CommitmentConfig, instruction::AccountMeta,
+        native_token::lamports_to_sol, program_pack::Pack, signature::read_keypair_file,
+        signer::Signer,
     },
     Client, Cluster,
 };
 use anchor_lang::{system_program, AnchorDeserialize};
-use clap::{Parser, Subcommand};
-use solana_program::pubkey::Pubkey;
-use voting::Proposal;
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    metadata::mpl_token_metadata::accounts::Metadata, token::spl_token,
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use solana_program::{hash::Hash, pubkey::Pubkey, system_instruction};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use voting::{
+    merkle, ApprovalThreshold, Chamber, CouncilMembership, Governance, GovernanceConfig,
+    MembershipProof, Proposal, ProposalInstruction, ProposalTransaction, Quorum, User, VoteChoice,
+    VotingMode,
+};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -25,12 +43,112 @@ enum Commands {
     CreateGovernance {
         /// Superteam
         name: String,
+
+        #[command(flatten)]
+        config: ConfigArgs,
+    },
+
+    /// Update the governance config (governance authority only)
+    UpdateGovernanceConfig {
+        /// Superteam
+        name: String,
+
+        #[command(flatten)]
+        config: ConfigArgs,
     },
 
     /// Join the governance
     Join {
         /// Superteam
         name: String,
+
+        /// NFT of the collection, required when the governance is gated on one
+        #[arg(long)]
+        nft_mint: Option<Pubkey>,
+
+        /// Snapshot file written by membership-snapshot, required when the
+        /// governance has a membership root
+        #[arg(long)]
+        snapshot: Option<String>,
+    },
+
+    /// Build the membership snapshot tree and proofs from a CSV of WALLET[,WEIGHT] lines
+    MembershipSnapshot {
+        /// CSV of the eligible wallets, the weight is 1 when omitted
+        csv: String,
+
+        /// Snapshot file to write, passed to join
+        out: String,
+    },
+
+    /// Delegate your voting power to another member
+    Delegate {
+        /// Superteam
+        name: String,
+
+        /// Wallet of the member voting on your behalf
+        delegate: Pubkey,
+    },
+
+    /// Take your voting power back from your delegate
+    Undelegate {
+        /// Superteam
+        name: String,
+    },
+
+    /// Deposit community tokens into the member vault
+    Deposit {
+        /// Superteam
+        name: String,
+
+        /// Amount in base units, taken from the associated token account
+        amount: u64,
+    },
+
+    /// Withdraw community tokens from the member vault
+    Withdraw {
+        /// Superteam
+        name: String,
+
+        /// Amount in base units, returned to the associated token account
+        amount: u64,
+    },
+
+    /// Lock deposited community tokens for voting power in the vote escrow mode
+    CreateLockup {
+        /// Superteam
+        name: String,
+
+        /// Amount in base units, taken from the deposit
+        amount: u64,
+
+        /// Lockup duration in seconds
+        duration: i64,
+    },
+
+    /// Move the unlock time of the lockup to now + duration
+    ExtendLockup {
+        /// Superteam
+        name: String,
+
+        /// Lockup duration in seconds
+        duration: i64,
+    },
+
+    /// Return the tokens of an expired lockup to the deposit
+    Unlock {
+        /// Superteam
+        name: String,
+    },
+
+    /// Create the allowlist voter weight plugin registrar of a governance
+    CreateAllowlist {
+        /// Superteam
+        name: String,
+
+        /// Allowlisted wallet and its vote weight, as WALLET:WEIGHT
+        #[arg(required = true, value_parser = parse_allowlist_entry)]
+        entries: Vec<AllowlistEntry>,
     },
 
     /// Create a proposal
@@ -40,6 +158,73 @@ enum Commands {
 
         /// Proposal title
         title: String,
+
+        /// Option label, repeat for each option
+        #[arg(long = "option", default_values_t = ["Yes".to_string(), "No".to_string()])]
+        options: Vec<String>,
+
+        /// Chamber voting on the proposal
+        #[arg(long, value_enum, default_value_t = ChamberArg::Community)]
+        chamber: ChamberArg,
+    },
+
+    /// Attach a transaction to an option of a draft proposal (proposal creator only)
+    InsertTransaction {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+
+        /// Option index executing the transaction if it wins
+        option: u8,
+
+        /// Number of transactions the option already has
+        index: u16,
+
+        /// Base64 of a borsh serialized ProposalInstruction, repeated for each instruction
+        #[arg(long = "instruction", required = true, value_parser = parse_proposal_instruction)]
+        instructions: Vec<ProposalInstruction>,
+    },
+
+    /// Attach a transfer out of the treasury to an option of a draft proposal (proposal creator only)
+    InsertTransfer {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+
+        /// Option index executing the transfer if it wins
+        option: u8,
+
+        /// Number of transactions the option already has
+        index: u16,
+
+        /// Recipient wallet
+        recipient: Pubkey,
+
+        /// Amount in lamports, or in base units with --mint
+        amount: u64,
+
+        /// Transfer tokens of this mint instead of SOL, to the associated token account of the recipient
+        #[arg(long)]
+        mint: Option<Pubkey>,
+    },
+
+    /// Attach a new governance config to an option of a draft proposal (proposal creator only)
+    InsertConfigChange {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+
+        /// Option index applying the config if it wins
+        option: u8,
+
+        #[command(flatten)]
+        config: ConfigArgs,
     },
 
     /// Start the voting
@@ -63,11 +248,62 @@ enum Commands {
         /// Proposal title
         title: String,
 
-        /// 1 => Yes, 0 => No
-        vote: u8,
+        /// Option index (0 => Yes, 1 => No on a yes/no proposal) or "abstain"
+        #[arg(value_parser = parse_vote_choice)]
+        vote: VoteChoice,
+
+        /// Derive the salt from this secret instead of a random salt. The vote is only as
+        /// hidden as the secret is hard to guess
+        #[arg(long)]
+        secret: Option<String>,
+
+        /// Voice credits spent on the vote, quadratic voting mode only
+        #[arg(long, default_value_t = 0)]
+        credits: u64,
+    },
+
+    /// List the members of the council chamber (governance authority only)
+    CreateCouncil {
+        /// Superteam
+        name: String,
+
+        /// Council member wallet, repeated for each member
+        #[arg(required = true)]
+        members: Vec<Pubkey>,
+    },
+
+    /// Commit your council vote, escrowing council tokens in a council of token holders
+    CommitCouncilVote {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+
+        /// Option index (0 => Yes, 1 => No on a yes/no proposal) or "abstain"
+        #[arg(value_parser = parse_vote_choice)]
+        vote: VoteChoice,
+
+        /// Derive the salt from this secret instead of a random salt. The vote is only as
+        /// hidden as the secret is hard to guess
+        #[arg(long)]
+        secret: Option<String>,
+
+        /// Council tokens escrowed on the vote in base units, taken from the associated token account
+        #[arg(long, default_value_t = 0)]
+        amount: u64,
+    },
+
+    /// Add the vote of an NFT of the collection to your commitment
+    RegisterNftVote {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
 
-        /// salt
-        salt: String,
+        /// NFT mint
+        mint: Pubkey,
     },
 
     /// Reveal vote
@@ -78,11 +314,126 @@ enum Commands {
         /// Proposal title
         title: String,
 
-        /// 1 => Yes, 0 => No
-        vote: u8,
+        /// Option index (0 => Yes, 1 => No on a yes/no proposal) or "abstain"
+        #[arg(value_parser = parse_vote_choice)]
+        vote: VoteChoice,
+
+        /// Salt printed by the commit
+        #[arg(required_unless_present = "secret")]
+        salt: Option<Hash>,
+
+        /// Secret passed to the commit instead of a random salt
+        #[arg(long, conflicts_with = "salt")]
+        secret: Option<String>,
+
+        /// Voice credits spent on the vote, quadratic voting mode only
+        #[arg(long, default_value_t = 0)]
+        credits: u64,
+    },
+
+    /// Reveal council vote
+    RevealCouncilVote {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+
+        /// Option index (0 => Yes, 1 => No on a yes/no proposal) or "abstain"
+        #[arg(value_parser = parse_vote_choice)]
+        vote: VoteChoice,
+
+        /// Salt printed by the commit
+        #[arg(required_unless_present = "secret")]
+        salt: Option<Hash>,
+
+        /// Secret passed to the commit instead of a random salt
+        #[arg(long, conflicts_with = "salt")]
+        secret: Option<String>,
+    },
+
+    /// Finalize the proposal after the reveal period
+    FinalizeProposal {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+    },
+
+    /// Mark a succeeded proposal expired once its grace period ended unexecuted
+    ExpireProposal {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+    },
+
+    /// Cancel your proposal while it is a draft or its voting period is running
+    CancelProposal {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+    },
+
+    /// Veto a proposal whose execution has not started (governance council only)
+    VetoProposal {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+    },
+
+    /// Execute the next transaction of the winning option of a succeeded proposal
+    ExecuteProposal {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+
+        /// Winning option index
+        option: u8,
+
+        /// Transaction index
+        index: u16,
+    },
+
+    /// Apply the config change of the winning option of a succeeded proposal
+    ExecuteConfigChange {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+    },
+
+    /// Close the vote commitment of a finalized proposal and refund its rent
+    CloseVoteCommitment {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+    },
+
+    /// Close the council vote of a finalized proposal, returning the escrowed council tokens
+    CloseCouncilVote {
+        /// Superteam
+        name: String,
+
+        /// Proposal title
+        title: String,
+    },
 
-        /// salt
-        salt: String,
+    /// Show the treasury address and balances
+    Treasury {
+        /// Superteam
+        name: String,
     },
 
     /// Show the result
@@ -95,6 +446,275 @@ enum Commands {
     },
 }
 
+#[derive(Args)]
+struct ConfigArgs {
+    /// Minimum voting period in seconds
+    #[arg(long, default_value_t = GovernanceConfig::default().min_voting_period)]
+    min_voting_period: i64,
+
+    /// Maximum voting period in seconds
+    #[arg(long, default_value_t = GovernanceConfig::default().max_voting_period)]
+    max_voting_period: i64,
+
+    /// Reveal window in seconds after the voting ends
+    #[arg(long, default_value_t = GovernanceConfig::default().reveal_period)]
+    reveal_period: i64,
+
+    /// Minimum number of votes for a proposal to be valid (default: 1)
+    #[arg(long, conflicts_with = "quorum_bps")]
+    quorum_votes: Option<u64>,
+
+    /// Minimum share of the members voting, in basis points (2000 => 20%)
+    #[arg(long)]
+    quorum_bps: Option<u16>,
+
+    /// Supermajority needed to approve, in basis points (6667 => 2/3).
+    /// A simple majority is enough when omitted
+    #[arg(long)]
+    supermajority_bps: Option<u16>,
+
+    /// How the weight of a vote is computed
+    #[arg(long, value_enum, default_value_t = VotingModeArg::Member)]
+    voting_mode: VotingModeArg,
+
+    /// Community token mint, required by the token, quadratic and escrow voting modes
+    #[arg(long)]
+    community_mint: Option<Pubkey>,
+
+    /// Lockup duration in seconds giving the full voting power in the escrow voting mode
+    #[arg(long, default_value_t = GovernanceConfig::default().max_lockup_period)]
+    max_lockup_period: i64,
+
+    /// Voter weight plugin program, overrides the voting mode
+    #[arg(long)]
+    voter_weight_plugin: Option<Pubkey>,
+
+    /// NFT collection gating membership, required by the nft voting mode
+    #[arg(long)]
+    nft_collection: Option<Pubkey>,
+
+    /// Merkle root of the wallets allowed to join, printed by membership-snapshot
+    #[arg(long)]
+    membership_root: Option<Hash>,
+
+    /// Timelock in seconds between the success of a proposal and its execution
+    #[arg(long, default_value_t = GovernanceConfig::default().execution_delay)]
+    execution_delay: i64,
+
+    /// Time in seconds after the timelock a proposal can be executed in, without limit when omitted
+    #[arg(long)]
+    execution_grace_period: Option<i64>,
+
+    /// Council key holding a veto over proposals until their execution starts
+    #[arg(long)]
+    council: Option<Pubkey>,
+
+    /// Add a council chamber of the wallets listed by create-council
+    #[arg(long, conflicts_with = "council_mint")]
+    council_members: bool,
+
+    /// Add a council chamber of the holders of this mint
+    #[arg(long)]
+    council_mint: Option<Pubkey>,
+
+    /// Minimum number of council votes for a proposal to pass the council (default: 1)
+    #[arg(long, conflicts_with = "council_quorum_bps")]
+    council_quorum_votes: Option<u64>,
+
+    /// Minimum share of the council voting, in basis points (5000 => 50%)
+    #[arg(long)]
+    council_quorum_bps: Option<u16>,
+
+    /// Supermajority of the council votes needed to approve, in basis points (6667 => 2/3).
+    /// A simple majority is enough when omitted
+    #[arg(long)]
+    council_supermajority_bps: Option<u16>,
+
+    /// Only config change proposals can update the config, not the governance authority
+    #[arg(long)]
+    disable_authority_updates: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum VotingModeArg {
+    /// One vote per member, or its weight in the membership snapshot
+    Member,
+    /// Weighted by the deposited community tokens
+    Token,
+    /// Square root of the voice credits spent, out of the deposited community tokens
+    Quadratic,
+    /// Locked community tokens, decaying linearly toward the unlock
+    Escrow,
+    /// One vote per NFT of the collection
+    Nft,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ChamberArg {
+    /// Community members only
+    Community,
+    /// Council chamber only
+    Council,
+    /// Both chambers, passing the same option
+    Both,
+}
+
+impl From<ChamberArg> for Chamber {
+    fn from(chamber: ChamberArg) -> Self {
+        match chamber {
+            ChamberArg::Community => Chamber::Community,
+            ChamberArg::Council => Chamber::Council,
+            ChamberArg::Both => Chamber::Both,
+        }
+    }
+}
+
+impl From<VotingModeArg> for VotingMode {
+    fn from(mode: VotingModeArg) -> Self {
+        match mode {
+            VotingModeArg::Member => VotingMode::OneMemberOneVote,
+            VotingModeArg::Token => VotingMode::TokenWeighted,
+            VotingModeArg::Quadratic => VotingMode::Quadratic,
+            VotingModeArg::Escrow => VotingMode::VoteEscrow,
+            VotingModeArg::Nft => VotingMode::NftWeighted,
+        }
+    }
+}
+
+impl From<&ConfigArgs> for GovernanceConfig {
+    fn from(args: &ConfigArgs) -> Self {
+        Self {
+            min_voting_period: args.min_voting_period,
+            max_voting_period: args.max_voting_period,
+            reveal_period: args.reveal_period,
+            quorum: match (args.quorum_votes, args.quorum_bps) {
+                (_, Some(bps)) => Quorum::Percentage(bps),
+                (Some(votes), None) => Quorum::Absolute(votes),
+                (None, None) => GovernanceConfig::default().quorum,
+            },
+            approval_threshold: match args.supermajority_bps {
+                Some(bps) => ApprovalThreshold::Supermajority(bps),
+                None => ApprovalThreshold::SimpleMajority,
+            },
+            voting_mode: args.voting_mode.into(),
+            community_mint: args.community_mint,
+            max_lockup_period: args.max_lockup_period,
+            voter_weight_plugin: args.voter_weight_plugin,
+            nft_collection: args.nft_collection,
+            membership_root: args.membership_root.map(|root| root.to_bytes()),
+            execution_delay: args.execution_delay,
+            execution_grace_period: args.execution_grace_period,
+            council: args.council,
+            council_chamber: match (args.council_members, args.council_mint) {
+                (_, Some(mint)) => Some(CouncilMembership::Mint(mint)),
+                (true, None) => Some(CouncilMembership::Members),
+                (false, None) => None,
+            },
+            council_quorum: match (args.council_quorum_votes, args.council_quorum_bps) {
+                (_, Some(bps)) => Quorum::Percentage(bps),
+                (Some(votes), None) => Quorum::Absolute(votes),
+                (None, None) => GovernanceConfig::default().council_quorum,
+            },
+            council_approval_threshold: match args.council_supermajority_bps {
+                Some(bps) => ApprovalThreshold::Supermajority(bps),
+                None => ApprovalThreshold::SimpleMajority,
+            },
+            allow_authority_updates: !args.disable_authority_updates,
+        }
+    }
+}
+
+fn parse_proposal_instruction(instruction: &str) -> Result<ProposalInstruction, String> {
+    let bytes = BASE64_STANDARD
+        .decode(instruction)
+        .map_err(|err| format!("invalid base64: {err}"))?;
+
+    ProposalInstruction::try_from_slice(&bytes).map_err(|err| format!("invalid instruction: {err}"))
+}
+
+fn parse_vote_choice(vote: &str) -> Result<VoteChoice, String> {
+    if vote.eq_ignore_ascii_case("abstain") {
+        return Ok(VoteChoice::Abstain);
+    }
+
+    vote.parse()
+        .map(VoteChoice::Option)
+        .map_err(|_| format!("expected an option index or \"abstain\", got {vote}"))
+}
+
+fn parse_allowlist_entry(entry: &str) -> Result<AllowlistEntry, String> {
+    let (voter, weight) = entry
+        .split_once(':')
+        .ok_or_else(|| format!("expected WALLET:WEIGHT, got {entry}"))?;
+
+    Ok(AllowlistEntry {
+        voter: voter
+            .parse()
+            .map_err(|_| format!("invalid wallet {voter}"))?,
+        weight: weight
+            .parse()
+            .map_err(|_| format!("invalid weight {weight}"))?,
+    })
+}
+
+/// Membership snapshot written by membership-snapshot and read by join
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    root: String,
+    members: Vec<SnapshotMember>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotMember {
+    wallet: String,
+    weight: u64,
+    proof: Vec<String>,
+}
+
+fn read_members(csv: &str) -> anyhow::Result<Vec<(Pubkey, u64)>> {
+    let mut wallets = HashSet::new();
+    let mut members = Vec::new();
+    for (number, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut fields = line.split(',').map(str::trim);
+        let wallet = fields.next().unwrap_or_default();
+        let wallet = Pubkey::from_str(wallet)
+            .map_err(|_| anyhow::anyhow!("line {}: invalid wallet {wallet}", number + 1))?;
+        let weight = match fields.next() {
+            Some(weight) => weight
+                .parse()
+                .map_err(|_| anyhow::anyhow!("line {}: invalid weight {weight}", number + 1))?,
+            None => 1,
+        };
+        if !wallets.insert(wallet) {
+            anyhow::bail!("line {}: duplicate wallet {wallet}", number + 1);
+        }
+        members.push((wallet, weight));
+    }
+
+    Ok(members)
+}
+
+/// Salt of a new vote commitment: random unless the voter opts into a secret.
+fn commit_salt(secret: Option<&str>) -> [u8; 32] {
+    match secret {
+        Some(secret) => voting::commitment::salt_from_secret(secret),
+        None => rand::random(),
+    }
+}
+
+fn reveal_salt(salt: Option<Hash>, secret: Option<&str>) -> [u8; 32] {
+    match (salt, secret) {
+        (_, Some(secret)) => voting::commitment::salt_from_secret(secret),
+        (Some(salt), None) => salt.to_bytes(),
+        (None, None) => unreachable!("clap requires the salt or the secret"),
+    }
+}
+
 fn main() {
     let program_id = "CaCJAg3ifFiGyVKYxZr4QwH2R9RvrDiVEgPntzXhXVP3";
 
@@ -105,102 +725,855 @@ fn main() {
     let program_id = Pubkey::from_str(program_id).unwrap();
     let program = client.program(program_id).unwrap();
 
-    let cli = Cli::parse();
-    match &cli.command {
-        Commands::CreateGovernance { name } => {
+    let cli = Cli::parse();
+    match &cli.command {
+        Commands::CreateGovernance { name, config } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::CreateGovernance {
+                    governance: governance_pda,
+                    authority: payer.pubkey(),
+                    system_program: system_program::ID,
+                })
+                .args(voting::instruction::CreateGovernance {
+                    name: name.to_string(),
+                    config: config.into(),
+                })
+                .send()
+                .expect("Failed to send create governance transaction");
+
+            println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::UpdateGovernanceConfig { name, config } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::UpdateGovernanceConfig {
+                    governance: governance_pda,
+                    authority: payer.pubkey(),
+                })
+                .args(voting::instruction::UpdateGovernanceConfig {
+                    config: config.into(),
+                })
+                .send()
+                .expect("Failed to send update governance config transaction");
+
+            println!("Successfully updated: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::Join {
+            name,
+            nft_mint,
+            snapshot,
+        } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+            let membership = snapshot.as_ref().map(|path| {
+                let snapshot: Snapshot = serde_json::from_str(
+                    &fs::read_to_string(path).expect("Failed to read the snapshot file"),
+                )
+                .expect("Invalid snapshot file");
+                let member = snapshot
+                    .members
+                    .into_iter()
+                    .find(|member| member.wallet == payer.pubkey().to_string())
+                    .expect("Wallet is not in the membership snapshot");
+
+                MembershipProof {
+                    weight: member.weight,
+                    proof: member
+                        .proof
+                        .iter()
+                        .map(|node| Hash::from_str(node).expect("Invalid proof").to_bytes())
+                        .collect(),
+                }
+            });
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::Join {
+                    governance: governance_pda,
+                    user: user_pda,
+                    nft_token_account: nft_mint
+                        .map(|mint| get_associated_token_address(&payer.pubkey(), &mint)),
+                    nft_metadata: nft_mint.map(|mint| Metadata::find_pda(&mint).0),
+                    member_nft_record: nft_mint.map(|mint| {
+                        Pubkey::find_program_address(
+                            &[b"member_nft", governance_pda.as_ref(), mint.as_ref()],
+                            &program_id,
+                        )
+                        .0
+                    }),
+                    authority: payer.pubkey(),
+                    system_program: system_program::ID,
+                })
+                .args(voting::instruction::Join { membership })
+                .send()
+                .expect("Failed to send join transaction");
+
+            println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::MembershipSnapshot { csv, out } => {
+            let members = read_members(&fs::read_to_string(csv).expect("Failed to read the CSV"))
+                .expect("Invalid CSV");
+            let levels = merkle::build_tree(
+                members
+                    .iter()
+                    .map(|(wallet, weight)| merkle::hash_member(wallet, *weight))
+                    .collect(),
+            );
+            let root = merkle::root(&levels).expect("The CSV has no wallet");
+
+            let snapshot = Snapshot {
+                root: Hash::new_from_array(root).to_string(),
+                members: members
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (wallet, weight))| SnapshotMember {
+                        wallet: wallet.to_string(),
+                        weight: *weight,
+                        proof: merkle::proof(&levels, index)
+                            .into_iter()
+                            .map(|node| Hash::new_from_array(node).to_string())
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            fs::write(out, serde_json::to_string_pretty(&snapshot).unwrap())
+                .expect("Failed to write the snapshot file");
+
+            println!(
+                "Snapshot of {} members written to {out}, membership root: {}",
+                members.len(),
+                snapshot.root
+            );
+        }
+        Commands::Delegate { name, delegate } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+            let (delegate_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), delegate.as_ref()],
+                &program_id,
+            );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::Delegate {
+                    governance: governance_pda,
+                    user: user_pda,
+                    delegate: delegate_pda,
+                    authority: payer.pubkey(),
+                })
+                .args(voting::instruction::Delegate {})
+                .send()
+                .expect("Failed to send delegate transaction");
+
+            println!("Successfully delegated: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::Undelegate { name } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+
+            let user: User = program.account(user_pda).expect("get user account");
+            let delegate = user.delegate.expect("voting power is not delegated");
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::Undelegate {
+                    governance: governance_pda,
+                    user: user_pda,
+                    delegate,
+                    authority: payer.pubkey(),
+                })
+                .args(voting::instruction::Undelegate {})
+                .send()
+                .expect("Failed to send undelegate transaction");
+
+            println!("Successfully undelegated: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::Deposit { name, amount } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+            let (vault_pda, _bump) = Pubkey::find_program_address(
+                &[b"vault", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+
+            let governance: voting::Governance = program
+                .account(governance_pda)
+                .expect("get governance account");
+            let community_mint = governance
+                .config
+                .community_mint
+                .expect("governance has no community mint");
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::DepositGoverningTokens {
+                    governance: governance_pda,
+                    user: user_pda,
+                    community_mint,
+                    vault: vault_pda,
+                    source: get_associated_token_address(&payer.pubkey(), &community_mint),
+                    authority: payer.pubkey(),
+                    token_program: spl_token::ID,
+                    system_program: system_program::ID,
+                })
+                .args(voting::instruction::DepositGoverningTokens { amount: *amount })
+                .send()
+                .expect("Failed to send deposit transaction");
+
+            println!("Successfully deposited: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::Withdraw { name, amount } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+            let (vault_pda, _bump) = Pubkey::find_program_address(
+                &[b"vault", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+
+            let governance: voting::Governance = program
+                .account(governance_pda)
+                .expect("get governance account");
+            let community_mint = governance
+                .config
+                .community_mint
+                .expect("governance has no community mint");
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::WithdrawGoverningTokens {
+                    governance: governance_pda,
+                    user: user_pda,
+                    vault: vault_pda,
+                    destination: get_associated_token_address(&payer.pubkey(), &community_mint),
+                    authority: payer.pubkey(),
+                    token_program: spl_token::ID,
+                })
+                .args(voting::instruction::WithdrawGoverningTokens { amount: *amount })
+                .send()
+                .expect("Failed to send withdraw transaction");
+
+            println!("Successfully withdrew: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::CreateLockup {
+            name,
+            amount,
+            duration,
+        } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+            let (lockup_pda, _bump) =
+                Pubkey::find_program_address(&[b"lockup", user_pda.as_ref()], &program_id);
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::CreateLockup {
+                    governance: governance_pda,
+                    user: user_pda,
+                    lockup: lockup_pda,
+                    authority: payer.pubkey(),
+                    system_program: system_program::ID,
+                })
+                .args(voting::instruction::CreateLockup {
+                    amount: *amount,
+                    duration: *duration,
+                })
+                .send()
+                .expect("Failed to send create lockup transaction");
+
+            println!("Successfully locked: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::ExtendLockup { name, duration } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+            let (lockup_pda, _bump) =
+                Pubkey::find_program_address(&[b"lockup", user_pda.as_ref()], &program_id);
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::ExtendLockup {
+                    governance: governance_pda,
+                    user: user_pda,
+                    lockup: lockup_pda,
+                    authority: payer.pubkey(),
+                })
+                .args(voting::instruction::ExtendLockup {
+                    duration: *duration,
+                })
+                .send()
+                .expect("Failed to send extend lockup transaction");
+
+            println!("Successfully extended: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::Unlock { name } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+            let (lockup_pda, _bump) =
+                Pubkey::find_program_address(&[b"lockup", user_pda.as_ref()], &program_id);
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::Unlock {
+                    governance: governance_pda,
+                    user: user_pda,
+                    lockup: lockup_pda,
+                    authority: payer.pubkey(),
+                })
+                .args(voting::instruction::Unlock {})
+                .send()
+                .expect("Failed to send unlock transaction");
+
+            println!("Successfully unlocked: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::CreateAllowlist { name, entries } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (registrar_pda, _bump) = Pubkey::find_program_address(
+                &[b"registrar", governance_pda.as_ref()],
+                &allowlist_plugin::ID,
+            );
+
+            let sig = client
+                .program(allowlist_plugin::ID)
+                .unwrap()
+                .request()
+                .accounts(allowlist_plugin::accounts::CreateRegistrar {
+                    governance: governance_pda,
+                    registrar: registrar_pda,
+                    authority: payer.pubkey(),
+                    system_program: system_program::ID,
+                })
+                .args(allowlist_plugin::instruction::CreateRegistrar {
+                    entries: entries.clone(),
+                })
+                .send()
+                .expect("Failed to send create allowlist transaction");
+
+            println!("Successfully created: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::CreateProposal {
+            name,
+            title,
+            options,
+            chamber,
+        } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::CreateProposal {
+                    governance: governance_pda,
+                    member: user_pda,
+                    proposal: proposal_pda,
+                    user: payer.pubkey(),
+                    system_program: system_program::ID,
+                })
+                .args(voting::instruction::CreateProposal {
+                    title: title.as_bytes().to_vec(),
+                    options: options.clone(),
+                    chamber: (*chamber).into(),
+                })
+                .send()
+                .expect("Failed to send create proposal transaction");
+
+            println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::InsertTransaction {
+            name,
+            title,
+            option,
+            index,
+            instructions,
+        } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+            let (transaction_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"proposal_transaction",
+                    proposal_pda.as_ref(),
+                    &[*option],
+                    &index.to_le_bytes(),
+                ],
+                &program_id,
+            );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::InsertTransaction {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    proposal_transaction: transaction_pda,
+                    authority: payer.pubkey(),
+                    system_program: system_program::ID,
+                })
+                .args(voting::instruction::InsertTransaction {
+                    option: *option,
+                    index: *index,
+                    instructions: instructions.clone(),
+                })
+                .send()
+                .expect("Failed to send insert transaction transaction");
+
+            println!("Successfully inserted: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::InsertTransfer {
+            name,
+            title,
+            option,
+            index,
+            recipient,
+            amount,
+            mint,
+        } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+            let (transaction_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"proposal_transaction",
+                    proposal_pda.as_ref(),
+                    &[*option],
+                    &index.to_le_bytes(),
+                ],
+                &program_id,
+            );
+            let (treasury_pda, _bump) =
+                Pubkey::find_program_address(&[b"treasury", governance_pda.as_ref()], &program_id);
+
+            let instructions = match mint {
+                // The treasury pays for the token account of the recipient if missing
+                Some(mint) => vec![
+                    create_associated_token_account_idempotent(
+                        &treasury_pda,
+                        recipient,
+                        mint,
+                        &spl_token::ID,
+                    ),
+                    spl_token::instruction::transfer(
+                        &spl_token::ID,
+                        &get_associated_token_address(&treasury_pda, mint),
+                        &get_associated_token_address(recipient, mint),
+                        &treasury_pda,
+                        &[],
+                        *amount,
+                    )
+                    .unwrap(),
+                ],
+                None => vec![system_instruction::transfer(
+                    &treasury_pda,
+                    recipient,
+                    *amount,
+                )],
+            };
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::InsertTransaction {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    proposal_transaction: transaction_pda,
+                    authority: payer.pubkey(),
+                    system_program: system_program::ID,
+                })
+                .args(voting::instruction::InsertTransaction {
+                    option: *option,
+                    index: *index,
+                    instructions: instructions.into_iter().map(Into::into).collect(),
+                })
+                .send()
+                .expect("Failed to send insert transfer transaction");
+
+            println!("Successfully inserted: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::InsertConfigChange {
+            name,
+            title,
+            option,
+            config,
+        } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+            let (config_change_pda, _bump) = Pubkey::find_program_address(
+                &[b"config_change", proposal_pda.as_ref()],
+                &program_id,
+            );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::InsertConfigChange {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    config_change: config_change_pda,
+                    authority: payer.pubkey(),
+                    system_program: system_program::ID,
+                })
+                .args(voting::instruction::InsertConfigChange {
+                    option: *option,
+                    config: config.into(),
+                })
+                .send()
+                .expect("Failed to send insert config change transaction");
+
+            println!("Successfully inserted: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::StartVote { name, title, end } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+            let governance = program
+                .account::<Governance>(governance_pda)
+                .expect("get governance account");
+            let council_mint = match governance.config.council_chamber {
+                Some(CouncilMembership::Mint(mint)) => Some(mint),
+                _ => None,
+            };
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::StartVote {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    council_mint,
+                    authority: payer.pubkey(),
+                })
+                .args(voting::instruction::StartVote { end: *end })
+                .send()
+                .expect("Failed to send start vote transaction");
+
+            println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::CommitVote {
+            name,
+            title,
+            vote,
+            secret,
+            credits,
+        } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+            let (vote_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"commit_vote",
+                    governance_pda.as_ref(),
+                    proposal_pda.as_ref(),
+                    payer.pubkey().as_ref(),
+                ],
+                &program_id,
+            );
+
+            let salt = commit_salt(secret.as_deref());
+            let commitment = voting::commitment::hash_vote(
+                &proposal_pda,
+                &payer.pubkey(),
+                *vote,
+                *credits,
+                &salt,
+            );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::CommitVote {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    member: user_pda,
+                    vote_commitment: vote_pda,
+                    user: payer.pubkey(),
+                    system_program: system_program::ID,
+                })
+                .args(voting::instruction::CommitVote { commitment })
+                .send()
+                .expect("Failed to send commit vote transaction");
+
+            println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
+            if secret.is_none() {
+                println!(
+                    "Salt: {}, keep it to reveal the vote",
+                    Hash::new_from_array(salt)
+                );
+            }
+        }
+        Commands::CreateCouncil { name, members } => {
             let (governance_pda, _bump) =
                 Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (council_pda, _bump) =
+                Pubkey::find_program_address(&[b"council", governance_pda.as_ref()], &program_id);
 
             let sig = program
                 .request()
-                .accounts(voting::accounts::CreateGovernance {
+                .accounts(voting::accounts::CreateCouncil {
                     governance: governance_pda,
+                    council: council_pda,
                     authority: payer.pubkey(),
                     system_program: system_program::ID,
                 })
-                .args(voting::instruction::CreateGovernance {
-                    name: name.to_string(),
+                .args(voting::instruction::CreateCouncil {
+                    members: members.clone(),
                 })
                 .send()
-                .expect("Failed to send create governance transaction");
+                .expect("Failed to send create council transaction");
 
             println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
         }
-        Commands::Join { name } => {
+        Commands::CommitCouncilVote {
+            name,
+            title,
+            vote,
+            secret,
+            amount,
+        } => {
             let (governance_pda, _bump) =
                 Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
-            let (user_pda, _bump) = Pubkey::find_program_address(
-                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+            let (council_pda, _bump) =
+                Pubkey::find_program_address(&[b"council", governance_pda.as_ref()], &program_id);
+            let (vote_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"council_vote",
+                    proposal_pda.as_ref(),
+                    payer.pubkey().as_ref(),
+                ],
+                &program_id,
+            );
+            let (escrow_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"council_escrow",
+                    proposal_pda.as_ref(),
+                    payer.pubkey().as_ref(),
+                ],
                 &program_id,
             );
+            let governance = program
+                .account::<Governance>(governance_pda)
+                .expect("get governance account");
+
+            let salt = commit_salt(secret.as_deref());
+            let commitment =
+                voting::commitment::hash_vote(&proposal_pda, &payer.pubkey(), *vote, 0, &salt);
+
+            let accounts = match governance.config.council_chamber {
+                Some(CouncilMembership::Mint(mint)) => voting::accounts::CommitCouncilVote {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    council: None,
+                    vote_commitment: vote_pda,
+                    council_mint: Some(mint),
+                    source: Some(get_associated_token_address(&payer.pubkey(), &mint)),
+                    escrow: Some(escrow_pda),
+                    voter: payer.pubkey(),
+                    token_program: spl_token::ID,
+                    system_program: system_program::ID,
+                },
+                _ => voting::accounts::CommitCouncilVote {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    council: Some(council_pda),
+                    vote_commitment: vote_pda,
+                    council_mint: None,
+                    source: None,
+                    escrow: None,
+                    voter: payer.pubkey(),
+                    token_program: spl_token::ID,
+                    system_program: system_program::ID,
+                },
+            };
 
             let sig = program
                 .request()
-                .accounts(voting::accounts::Join {
-                    user: user_pda,
-                    authority: payer.pubkey(),
-                    system_program: system_program::ID,
-                })
-                .args(voting::instruction::Join {
-                    governance_key: governance_pda,
+                .accounts(accounts)
+                .args(voting::instruction::CommitCouncilVote {
+                    commitment,
+                    amount: *amount,
                 })
                 .send()
-                .expect("Failed to send join transaction");
+                .expect("Failed to send commit council vote transaction");
 
             println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
+            if secret.is_none() {
+                println!(
+                    "Salt: {}, keep it to reveal the vote",
+                    Hash::new_from_array(salt)
+                );
+            }
         }
-        Commands::CreateProposal { name, title } => {
+        Commands::RevealCouncilVote {
+            name,
+            title,
+            vote,
+            salt,
+            secret,
+        } => {
             let (governance_pda, _bump) =
                 Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
             let (proposal_pda, _bump) = Pubkey::find_program_address(
                 &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
                 &program_id,
             );
+            let (vote_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"council_vote",
+                    proposal_pda.as_ref(),
+                    payer.pubkey().as_ref(),
+                ],
+                &program_id,
+            );
+            let (escrow_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"council_escrow",
+                    proposal_pda.as_ref(),
+                    payer.pubkey().as_ref(),
+                ],
+                &program_id,
+            );
+            let escrow = program
+                .rpc()
+                .get_account(&escrow_pda)
+                .ok()
+                .map(|_| escrow_pda);
 
             let sig = program
                 .request()
-                .accounts(voting::accounts::CreateProposal {
+                .accounts(voting::accounts::RevealCouncilVote {
+                    governance: governance_pda,
                     proposal: proposal_pda,
-                    user: payer.pubkey(),
-                    system_program: system_program::ID,
+                    vote_commitment: vote_pda,
+                    escrow,
+                    voter: payer.pubkey(),
                 })
-                .args(voting::instruction::CreateProposal {
-                    governance_key: governance_pda,
-                    title: title.as_bytes().to_vec(),
+                .args(voting::instruction::RevealCouncilVote {
+                    choice: *vote,
+                    salt: reveal_salt(*salt, secret.as_deref()),
                 })
                 .send()
-                .expect("Failed to send create proposal transaction");
+                .expect("Failed to send reveal council vote transaction");
 
-            println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
+            println!("Successfully revealed: https://solscan.io/tx/{sig}?cluster=devnet");
         }
-        Commands::StartVote { name, title, end } => {
+        Commands::RegisterNftVote { name, title, mint } => {
             let (governance_pda, _bump) =
                 Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
-
             let (proposal_pda, _bump) = Pubkey::find_program_address(
                 &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
                 &program_id,
             );
+            let (vote_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"commit_vote",
+                    governance_pda.as_ref(),
+                    proposal_pda.as_ref(),
+                    payer.pubkey().as_ref(),
+                ],
+                &program_id,
+            );
+            let (nft_vote_pda, _bump) = Pubkey::find_program_address(
+                &[b"nft_vote", proposal_pda.as_ref(), mint.as_ref()],
+                &program_id,
+            );
 
             let sig = program
                 .request()
-                .accounts(voting::accounts::StartVote {
+                .accounts(voting::accounts::RegisterNftVote {
+                    governance: governance_pda,
                     proposal: proposal_pda,
+                    vote_commitment: vote_pda,
+                    nft_token_account: get_associated_token_address(&payer.pubkey(), mint),
+                    nft_metadata: Metadata::find_pda(mint).0,
+                    nft_vote_record: nft_vote_pda,
+                    voter: payer.pubkey(),
+                    system_program: system_program::ID,
                 })
-                .args(voting::instruction::StartVote { end: *end })
+                .args(voting::instruction::RegisterNftVote {})
                 .send()
-                .expect("Failed to send start vote transaction");
+                .expect("Failed to send register nft vote transaction");
 
-            println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
+            println!("Successfully registered: https://solscan.io/tx/{sig}?cluster=devnet");
         }
-        Commands::CommitVote {
+        Commands::RevealVote {
             name,
             title,
             vote,
             salt,
+            secret,
+            credits,
         } => {
             let (governance_pda, _bump) =
                 Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
             let (proposal_pda, _bump) = Pubkey::find_program_address(
                 &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
                 &program_id,
@@ -214,40 +1587,254 @@ fn main() {
                 ],
                 &program_id,
             );
+            let (lockup_pda, _bump) =
+                Pubkey::find_program_address(&[b"lockup", user_pda.as_ref()], &program_id);
+            let lockup = program
+                .account::<voting::Lockup>(lockup_pda)
+                .ok()
+                .map(|_| lockup_pda);
 
-            let vote = *vote == 1;
-            let temp = format!("{}{}", vote, salt);
-            let commitment = solana_program::hash::hash(temp.as_bytes());
+            let mut delegate = vec![1];
+            delegate.extend_from_slice(user_pda.as_ref());
+            let delegators = program
+                .accounts::<User>(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    User::DELEGATE_OFFSET,
+                    delegate,
+                ))])
+                .expect("get delegators")
+                .into_iter()
+                .map(|(delegator, _)| AccountMeta::new_readonly(delegator, false))
+                .collect::<Vec<_>>();
 
-            let sig = program
-                .request()
-                .accounts(voting::accounts::CommitVote {
+            // Plugins keep the record of a voter at this address by convention,
+            // the allowlist plugin record is refreshed in the same transaction
+            let governance: voting::Governance = program
+                .account(governance_pda)
+                .expect("get governance account");
+            let mut request = program.request();
+            let voter_weight_record = governance.config.voter_weight_plugin.map(|plugin| {
+                let (voter_weight_record_pda, _bump) = Pubkey::find_program_address(
+                    &[
+                        b"voter_weight_record",
+                        governance_pda.as_ref(),
+                        payer.pubkey().as_ref(),
+                    ],
+                    &plugin,
+                );
+
+                voter_weight_record_pda
+            });
+            if let Some(voter_weight_record_pda) = voter_weight_record
+                .filter(|_| governance.config.voter_weight_plugin == Some(allowlist_plugin::ID))
+            {
+                let (registrar_pda, _bump) = Pubkey::find_program_address(
+                    &[b"registrar", governance_pda.as_ref()],
+                    &allowlist_plugin::ID,
+                );
+                let update_ixs = client
+                    .program(allowlist_plugin::ID)
+                    .unwrap()
+                    .request()
+                    .accounts(allowlist_plugin::accounts::UpdateVoterWeightRecord {
+                        registrar: registrar_pda,
+                        voter_weight_record: voter_weight_record_pda,
+                        voter: payer.pubkey(),
+                        system_program: system_program::ID,
+                    })
+                    .args(allowlist_plugin::instruction::UpdateVoterWeightRecord {})
+                    .instructions()
+                    .expect("build update voter weight record instruction");
+                for ix in update_ixs {
+                    request = request.instruction(ix);
+                }
+            }
+
+            let sig = request
+                .accounts(voting::accounts::RevealVote {
                     governance: governance_pda,
                     proposal: proposal_pda,
                     vote_commitment: vote_pda,
-                    user: payer.pubkey(),
-                    system_program: system_program::ID,
+                    user: user_pda,
+                    lockup,
+                    voter_weight_record,
+                    voter: payer.pubkey(),
                 })
-                .args(voting::instruction::CommitVote {
-                    commitment: commitment.to_string(),
+                .accounts(delegators)
+                .args(voting::instruction::RevealVote {
+                    choice: *vote,
+                    credits: *credits,
+                    salt: reveal_salt(*salt, secret.as_deref()),
                 })
                 .send()
-                .expect("Failed to send commit vote transaction");
+                .expect("Failed to send reveal vote transaction");
 
             println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
         }
-        Commands::RevealVote {
+        Commands::FinalizeProposal { name, title } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::FinalizeProposal {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                })
+                .args(voting::instruction::FinalizeProposal {})
+                .send()
+                .expect("Failed to send finalize proposal transaction");
+
+            println!("Successfully finalized: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::ExpireProposal { name, title } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::ExpireProposal {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                })
+                .args(voting::instruction::ExpireProposal {})
+                .send()
+                .expect("Failed to send expire proposal transaction");
+
+            println!("Successfully expired: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::CancelProposal { name, title } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::CancelProposal {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    authority: payer.pubkey(),
+                })
+                .args(voting::instruction::CancelProposal {})
+                .send()
+                .expect("Failed to send cancel proposal transaction");
+
+            println!("Successfully cancelled: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::VetoProposal { name, title } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::VetoProposal {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    council: payer.pubkey(),
+                })
+                .args(voting::instruction::VetoProposal {})
+                .send()
+                .expect("Failed to send veto proposal transaction");
+
+            println!("Successfully vetoed: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::ExecuteProposal {
             name,
             title,
-            vote,
-            salt,
+            option,
+            index,
         } => {
             let (governance_pda, _bump) =
                 Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
-            let (user_pda, _bump) = Pubkey::find_program_address(
-                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+            let (transaction_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"proposal_transaction",
+                    proposal_pda.as_ref(),
+                    &[*option],
+                    &index.to_le_bytes(),
+                ],
+                &program_id,
+            );
+            let (treasury, _bump) =
+                Pubkey::find_program_address(&[b"treasury", governance_pda.as_ref()], &program_id);
+
+            let transaction: ProposalTransaction = program
+                .account(transaction_pda)
+                .expect("Failed to fetch the proposal transaction");
+            let mut remaining_accounts = Vec::new();
+            for instruction in &transaction.instructions {
+                remaining_accounts.push(AccountMeta::new_readonly(instruction.program_id, false));
+                for meta in &instruction.accounts {
+                    // The treasury signs through the voting program
+                    let is_signer = meta.is_signer && meta.pubkey != treasury;
+                    remaining_accounts.push(match meta.is_writable {
+                        true => AccountMeta::new(meta.pubkey, is_signer),
+                        false => AccountMeta::new_readonly(meta.pubkey, is_signer),
+                    });
+                }
+            }
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::ExecuteProposal {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    proposal_transaction: transaction_pda,
+                    treasury,
+                })
+                .accounts(remaining_accounts)
+                .args(voting::instruction::ExecuteProposal {})
+                .send()
+                .expect("Failed to send execute proposal transaction");
+
+            println!("Successfully executed: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::ExecuteConfigChange { name, title } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+            let (config_change_pda, _bump) = Pubkey::find_program_address(
+                &[b"config_change", proposal_pda.as_ref()],
                 &program_id,
             );
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::ExecuteConfigChange {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    config_change: config_change_pda,
+                })
+                .args(voting::instruction::ExecuteConfigChange {})
+                .send()
+                .expect("Failed to send execute config change transaction");
+
+            println!("Successfully executed: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::CloseVoteCommitment { name, title } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
             let (proposal_pda, _bump) = Pubkey::find_program_address(
                 &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
                 &program_id,
@@ -261,23 +1848,112 @@ fn main() {
                 ],
                 &program_id,
             );
+            let (user_pda, _bump) = Pubkey::find_program_address(
+                &[b"user", governance_pda.as_ref(), payer.pubkey().as_ref()],
+                &program_id,
+            );
 
-            let vote = *vote == 1;
             let sig = program
                 .request()
-                .accounts(voting::accounts::RevealVote {
+                .accounts(voting::accounts::CloseVoteCommitment {
                     proposal: proposal_pda,
                     vote_commitment: vote_pda,
                     user: user_pda,
+                    voter: payer.pubkey(),
                 })
-                .args(voting::instruction::RevealVote {
-                    vote,
-                    salt: salt.to_string(),
+                .args(voting::instruction::CloseVoteCommitment {})
+                .send()
+                .expect("Failed to send close vote commitment transaction");
+
+            println!("Successfully closed: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::CloseCouncilVote { name, title } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (proposal_pda, _bump) = Pubkey::find_program_address(
+                &[b"proposal", governance_pda.as_ref(), title.as_bytes()],
+                &program_id,
+            );
+            let (vote_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"council_vote",
+                    proposal_pda.as_ref(),
+                    payer.pubkey().as_ref(),
+                ],
+                &program_id,
+            );
+            let (escrow_pda, _bump) = Pubkey::find_program_address(
+                &[
+                    b"council_escrow",
+                    proposal_pda.as_ref(),
+                    payer.pubkey().as_ref(),
+                ],
+                &program_id,
+            );
+            let governance = program
+                .account::<Governance>(governance_pda)
+                .expect("get governance account");
+            let (escrow, destination) = match governance.config.council_chamber {
+                Some(CouncilMembership::Mint(mint)) => (
+                    Some(escrow_pda),
+                    Some(get_associated_token_address(&payer.pubkey(), &mint)),
+                ),
+                _ => (None, None),
+            };
+
+            let sig = program
+                .request()
+                .accounts(voting::accounts::CloseCouncilVote {
+                    governance: governance_pda,
+                    proposal: proposal_pda,
+                    vote_commitment: vote_pda,
+                    escrow,
+                    destination,
+                    voter: payer.pubkey(),
+                    token_program: spl_token::ID,
                 })
+                .args(voting::instruction::CloseCouncilVote {})
                 .send()
-                .expect("Failed to send reveal vote transaction");
+                .expect("Failed to send close council vote transaction");
 
-            println!("Successfully initialized: https://solscan.io/tx/{sig}?cluster=devnet");
+            println!("Successfully closed: https://solscan.io/tx/{sig}?cluster=devnet");
+        }
+        Commands::Treasury { name } => {
+            let (governance_pda, _bump) =
+                Pubkey::find_program_address(&[b"governance", name.as_bytes()], &program_id);
+            let (treasury_pda, _bump) =
+                Pubkey::find_program_address(&[b"treasury", governance_pda.as_ref()], &program_id);
+
+            let rpc_client = program.rpc();
+            let lamports = rpc_client
+                .get_balance(&treasury_pda)
+                .expect("get treasury balance");
+            let token_accounts = rpc_client
+                .get_program_accounts_with_config(
+                    &spl_token::ID,
+                    RpcProgramAccountsConfig {
+                        filters: Some(vec![
+                            RpcFilterType::DataSize(spl_token::state::Account::LEN as u64),
+                            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                                32,
+                                treasury_pda.to_bytes().to_vec(),
+                            )),
+                        ]),
+                        ..RpcProgramAccountsConfig::default()
+                    },
+                )
+                .expect("get treasury token accounts");
+
+            println!("Treasury: {treasury_pda}");
+            println!("SOL: {}", lamports_to_sol(lamports));
+            for (address, account) in token_accounts {
+                let token_account =
+                    spl_token::state::Account::unpack(&account.data).expect("unpack token account");
+                println!(
+                    "{}: {} ({address})",
+                    token_account.mint, token_account.amount
+                );
+            }
         }
         Commands::ShowResult { name, title } => {
             let (governance_pda, _bump) =
@@ -293,9 +1969,32 @@ fn main() {
                 .get_account(&proposal_pda)
                 .expect("get proposal account");
             let proposal = Proposal::try_from_slice(&account.data[8..]).expect("deserialize data");
+            let slot = rpc_client.get_slot().expect("get slot");
+            let now = rpc_client.get_block_time(slot).expect("get cluster time");
 
-            println!("Votes for: {}", proposal.votes_for);
-            println!("Votes against: {}", proposal.votes_against);
+            println!("State: {:?}", proposal.current_state(now));
+            println!("Reveal end: {}", proposal.reveal_end);
+            for (index, (option, votes)) in
+                proposal.options.iter().zip(&proposal.tallies).enumerate()
+            {
+                println!("[{index}] {option}: {votes}");
+            }
+            println!("Abstain: {}", proposal.votes_abstain);
+            if proposal.chamber.includes_council() {
+                println!("Chamber: {:?}", proposal.chamber);
+                for (index, (option, votes)) in proposal
+                    .options
+                    .iter()
+                    .zip(&proposal.council_tallies)
+                    .enumerate()
+                {
+                    println!("Council [{index}] {option}: {votes}");
+                }
+                println!("Council abstain: {}", proposal.council_votes_abstain);
+            }
+            if let Some(winner) = proposal.winning_option {
+                println!("Winner: {}", proposal.options[winner as usize]);
+            }
         }
     }
 }
@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+
+declare_id!("p7tQtNFJyK7iPd4fGqhKhK3GH36CjG6qZCx9ruekWew");
+
+pub mod constants {
+    pub const REGISTRAR_SEED: &[u8] = b"registrar";
+    pub const VOTER_WEIGHT_RECORD_SEED: &[u8] = b"voter_weight_record";
+
+    pub const MAX_ENTRIES: usize = 32;
+}
+
+/// Reference voter weight plugin for the voting program.
+///
+/// A registrar holds a fixed list of wallets and their weights for one
+/// governance. Before revealing, a voter refreshes its `VoterWeightRecord` in
+/// the same transaction, the record expires at the end of the slot.
+#[program]
+pub mod allowlist_plugin {
+    use anchor_lang::{
+        context::Context,
+        solana_program::{clock::Clock, sysvar::Sysvar},
+        Key,
+    };
+
+    use crate::{AllowlistEntry, AllowlistErrorCode, CreateRegistrar, UpdateVoterWeightRecord};
+
+    /// Creates the registrar of a governance, signed by the governance
+    /// authority.
+    pub fn create_registrar(
+        ctx: Context<CreateRegistrar>,
+        entries: Vec<AllowlistEntry>,
+    ) -> anchor_lang::Result<()> {
+        if entries.len() > crate::constants::MAX_ENTRIES {
+            return Err(AllowlistErrorCode::TooManyEntries.into());
+        }
+
+        let registrar = &mut ctx.accounts.registrar;
+        registrar.governance = ctx.accounts.governance.key();
+        registrar.authority = ctx.accounts.authority.key();
+        registrar.entries = entries;
+
+        Ok(())
+    }
+
+    pub fn update_voter_weight_record(
+        ctx: Context<UpdateVoterWeightRecord>,
+    ) -> anchor_lang::Result<()> {
+        let registrar = &ctx.accounts.registrar;
+        let voter = ctx.accounts.voter.key();
+        let entry = registrar
+            .entries
+            .iter()
+            .find(|entry| entry.voter == voter)
+            .ok_or(AllowlistErrorCode::NotAllowlisted)?;
+
+        let voter_weight_record = &mut ctx.accounts.voter_weight_record;
+        voter_weight_record.governance = registrar.governance;
+        voter_weight_record.voter = voter;
+        voter_weight_record.voter_weight = entry.weight;
+        voter_weight_record.voter_weight_expiry = Some(Clock::get()?.slot);
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(entries: Vec<AllowlistEntry>)]
+pub struct CreateRegistrar<'info> {
+    /// Owned by the voting program
+    #[account(has_one = authority @ AllowlistErrorCode::Unauthorized)]
+    pub governance: Account<'info, voting::Governance>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::REGISTRAR_SEED, governance.key().as_ref()],
+        bump,
+        payer = authority,
+        space = Registrar::space(entries.len())
+    )]
+    pub registrar: Account<'info, Registrar>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVoterWeightRecord<'info> {
+    pub registrar: Account<'info, Registrar>,
+
+    #[account(
+        init_if_needed,
+        seeds = [crate::constants::VOTER_WEIGHT_RECORD_SEED, registrar.governance.as_ref(), voter.key().as_ref()],
+        bump,
+        payer = voter,
+        space = 8 + 32 + 32 + 8 + 1 + 8
+    )]
+    pub voter_weight_record: Account<'info, VoterWeightRecord>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct Registrar {
+    pub governance: Pubkey,
+    pub authority: Pubkey,
+    pub entries: Vec<AllowlistEntry>,
+}
+
+impl Registrar {
+    pub fn space(entries: usize) -> usize {
+        8 + 32 + 32 + 4 + entries * AllowlistEntry::LEN
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AllowlistEntry {
+    pub voter: Pubkey,
+    pub weight: u64,
+}
+
+impl AllowlistEntry {
+    pub const LEN: usize = 32 + 8;
+}
+
+/// Vote weight of a voter, read by the voting program at reveal time.
+///
+/// The voting program mirrors this layout, keep both in sync.
+#[account]
+pub struct VoterWeightRecord {
+    pub governance: Pubkey,
+    pub voter: Pubkey,
+    pub voter_weight: u64,
+    /// Last slot the weight can be used in
+    pub voter_weight_expiry: Option<u64>,
+}
+
+#[error_code]
+pub enum AllowlistErrorCode {
+    #[msg("Too many allowlist entries")]
+    TooManyEntries,
+
+    #[msg("Voter is not on the allowlist")]
+    NotAllowlisted,
+
+    #[msg("Signer is not the governance authority")]
+    Unauthorized,
+}
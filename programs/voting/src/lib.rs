@@ -1,4 +1,11 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    solana_program::instruction::{AccountMeta, Instruction},
+};
+use anchor_spl::{
+    metadata::MetadataAccount,
+    token::{Mint, Token, TokenAccount},
+};
 
 declare_id!("CaCJAg3ifFiGyVKYxZr4QwH2R9RvrDiVEgPntzXhXVP3");
 
@@ -7,88 +14,610 @@ pub mod constants {
     pub const USER_SEED: &[u8] = b"user";
     pub const PROPOSAL_SEED: &[u8] = b"proposal";
     pub const COMMIT_VOTE_SEED: &[u8] = b"commit_vote";
+    pub const VAULT_SEED: &[u8] = b"vault";
+    pub const LOCKUP_SEED: &[u8] = b"lockup";
+    pub const NFT_VOTE_SEED: &[u8] = b"nft_vote";
+    pub const MEMBER_NFT_SEED: &[u8] = b"member_nft";
+    pub const PROPOSAL_TRANSACTION_SEED: &[u8] = b"proposal_transaction";
+    pub const TREASURY_SEED: &[u8] = b"treasury";
+    pub const COUNCIL_SEED: &[u8] = b"council";
+    pub const COUNCIL_VOTE_SEED: &[u8] = b"council_vote";
+    pub const COUNCIL_ESCROW_SEED: &[u8] = b"council_escrow";
+    pub const CONFIG_CHANGE_SEED: &[u8] = b"config_change";
+
+    pub const MAX_OPTIONS: usize = 10;
+    pub const MAX_OPTION_LABEL_LEN: usize = 32;
+    pub const MAX_COUNCIL_MEMBERS: usize = 32;
+}
+
+/// Vote commitment encoding shared by the program and its clients.
+pub mod commitment {
+    use anchor_lang::solana_program::{hash, pubkey::Pubkey};
+
+    use crate::VoteChoice;
+
+    pub const COMMITMENT_TAG: &[u8] = b"voting:vote_commitment";
+    pub const COMMITMENT_VERSION: u8 = 3;
+
+    /// Hashes `tag || version || proposal || voter || choice || credits || salt`.
+    ///
+    /// Every field after the tag has a fixed width, so two different votes can
+    /// never share a preimage.
+    pub fn hash_vote(
+        proposal: &Pubkey,
+        voter: &Pubkey,
+        choice: VoteChoice,
+        credits: u64,
+        salt: &[u8; 32],
+    ) -> [u8; 32] {
+        hash::hashv(&[
+            COMMITMENT_TAG,
+            &[COMMITMENT_VERSION],
+            proposal.as_ref(),
+            voter.as_ref(),
+            &encode_choice(choice),
+            &credits.to_le_bytes(),
+            salt,
+        ])
+        .to_bytes()
+    }
+
+    fn encode_choice(choice: VoteChoice) -> [u8; 2] {
+        match choice {
+            VoteChoice::Option(index) => [0, index],
+            VoteChoice::Abstain => [1, 0],
+        }
+    }
+
+    /// Derives a 32 byte salt from a secret the voter can remember.
+    ///
+    /// The vote can be recovered from the commitment by guessing the secret,
+    /// clients generate a random salt unless the voter opts into one.
+    pub fn salt_from_secret(secret: &str) -> [u8; 32] {
+        hash::hash(secret.as_bytes()).to_bytes()
+    }
+}
+
+/// Membership snapshot Merkle tree shared by the program and its clients.
+///
+/// Leaves and inner nodes are hashed under different tags, so a node can never
+/// pass for a member. Pairs are sorted before hashing, so a proof is the list
+/// of siblings without their side.
+pub mod merkle {
+    use anchor_lang::solana_program::{hash, pubkey::Pubkey};
+
+    pub const MEMBER_TAG: &[u8] = b"voting:member";
+    pub const NODE_TAG: &[u8] = b"voting:node";
+
+    /// Hashes `tag || wallet || weight`.
+    pub fn hash_member(wallet: &Pubkey, weight: u64) -> [u8; 32] {
+        hash::hashv(&[MEMBER_TAG, wallet.as_ref(), &weight.to_le_bytes()]).to_bytes()
+    }
+
+    fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        hash::hashv(&[NODE_TAG, left, right]).to_bytes()
+    }
+
+    /// Checks `leaf` is in the tree of `root`.
+    pub fn verify(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+        proof
+            .iter()
+            .fold(leaf, |node, sibling| hash_pair(&node, sibling))
+            == *root
+    }
+
+    /// Levels of the tree of `leaves`, from the leaves up to the root. A node
+    /// without a sibling moves up unchanged.
+    pub fn build_tree(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+        let mut levels = vec![leaves];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let next = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_pair(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+
+        levels
+    }
+
+    pub fn root(levels: &[Vec<[u8; 32]>]) -> Option<[u8; 32]> {
+        levels.last()?.first().copied()
+    }
+
+    /// Siblings of the leaf at `index`, from the bottom of the tree.
+    pub fn proof(levels: &[Vec<[u8; 32]>], mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        for level in levels.iter().take(levels.len().saturating_sub(1)) {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+
+        proof
+    }
 }
 
 #[program]
 pub mod voting {
     use anchor_lang::{
-        context::Context,
-        solana_program::{self, clock::Clock, pubkey::Pubkey, sysvar::Sysvar},
+        context::{Context, CpiContext},
+        emit,
+        solana_program::{clock::Clock, program::invoke_signed, pubkey::Pubkey, sysvar::Sysvar},
+        AccountDeserialize, Key, ToAccountInfo,
     };
+    use anchor_spl::token::{self, CloseAccount, Transfer};
 
     use crate::{
-        CommitVote, CreateGovernance, CreateProposal, Join, RevealVote, StartVote, VotingErrorCode,
+        commitment,
+        constants::{MAX_COUNCIL_MEMBERS, MAX_OPTIONS, MAX_OPTION_LABEL_LEN},
+        merkle, verify_nft, CancelProposal, Chamber, CloseCouncilVote, CloseVoteCommitment,
+        CommitCouncilVote, CommitVote, CouncilMembership, CreateCouncil, CreateGovernance,
+        CreateLockup, CreateProposal, Delegate, DepositGoverningTokens, ExecuteConfigChange,
+        ExecuteProposal, ExpireProposal, ExtendLockup, FinalizeProposal, GovernanceConfig,
+        InsertConfigChange, InsertTransaction, Join, MembershipProof, ProposalCancelled,
+        ProposalInstruction, ProposalState, ProposalVetoed, RegisterNftVote, RevealCouncilVote,
+        RevealVote, StartVote, Undelegate, Unlock, UpdateGovernanceConfig, User, VetoProposal,
+        VoteChoice, VoterWeightRecord, VotingErrorCode, VotingMode, WithdrawGoverningTokens,
     };
 
     pub fn create_governance(
         ctx: Context<CreateGovernance>,
         name: String,
+        config: GovernanceConfig,
     ) -> anchor_lang::Result<()> {
+        config.validate()?;
+
+        // Only the governance authority lists the council members
+        if config.council_chamber == Some(CouncilMembership::Members)
+            && !config.allow_authority_updates
+        {
+            return Err(VotingErrorCode::InvalidChamber.into());
+        }
+
         let governance = &mut ctx.accounts.governance;
+        governance.authority = ctx.accounts.authority.key();
         governance.name = name;
+        governance.config = config;
+
+        Ok(())
+    }
+
+    pub fn update_governance_config(
+        ctx: Context<UpdateGovernanceConfig>,
+        config: GovernanceConfig,
+    ) -> anchor_lang::Result<()> {
+        let governance = &mut ctx.accounts.governance;
+        if !governance.config.allow_authority_updates {
+            return Err(VotingErrorCode::AuthorityUpdatesDisabled.into());
+        }
+
+        governance.check_update(&config)?;
+        governance.config = config;
 
         Ok(())
     }
 
-    pub fn join(ctx: Context<Join>, governance_key: Pubkey) -> anchor_lang::Result<()> {
+    /// Adds the signer as a member. A governance with an NFT collection
+    /// requires the token account and metadata account of an NFT of the
+    /// collection held by the signer, and a governance with a membership
+    /// snapshot a proof that the signer is in it.
+    pub fn join(
+        ctx: Context<Join>,
+        membership: Option<MembershipProof>,
+    ) -> anchor_lang::Result<()> {
+        if let Some(collection) = ctx.accounts.governance.config.nft_collection {
+            match (
+                &ctx.accounts.nft_token_account,
+                &ctx.accounts.nft_metadata,
+                &mut ctx.accounts.member_nft_record,
+            ) {
+                (Some(token_account), Some(metadata), Some(member_nft_record)) => {
+                    verify_nft(
+                        &collection,
+                        &ctx.accounts.authority.key(),
+                        token_account,
+                        metadata,
+                    )?;
+
+                    member_nft_record.governance = ctx.accounts.governance.key();
+                    member_nft_record.mint = token_account.mint;
+                    member_nft_record.member = ctx.accounts.authority.key();
+                }
+                _ => return Err(VotingErrorCode::InvalidNft.into()),
+            }
+        }
+
+        let membership_weight = match (ctx.accounts.governance.config.membership_root, membership) {
+            (Some(root), Some(membership)) => {
+                let leaf = merkle::hash_member(&ctx.accounts.authority.key(), membership.weight);
+                if !merkle::verify(&root, leaf, &membership.proof) {
+                    return Err(VotingErrorCode::NotEligible.into());
+                }
+                membership.weight
+            }
+            (Some(_), None) => return Err(VotingErrorCode::NotEligible.into()),
+            (None, _) => 1,
+        };
+
+        let governance = &mut ctx.accounts.governance;
+        governance.member_count = governance
+            .member_count
+            .checked_add(1)
+            .ok_or(VotingErrorCode::Overflow)?;
+        governance.total_membership_weight = governance
+            .total_membership_weight
+            .checked_add(membership_weight)
+            .ok_or(VotingErrorCode::Overflow)?;
+
         let user = &mut ctx.accounts.user;
+        user.governance = governance.key();
+        user.authority = ctx.accounts.authority.key();
         user.points = 0;
+        user.deposited_amount = 0;
+        user.delegate = None;
+        user.delegator_count = 0;
+        user.membership_weight = membership_weight;
+        user.credits_spent = 0;
+
+        Ok(())
+    }
+
+    /// Points the voting power of the member at another member.
+    ///
+    /// Delegation is a single hop: a delegate cannot delegate in turn and a
+    /// member with delegators cannot delegate, which rules out cycles.
+    pub fn delegate(ctx: Context<Delegate>) -> anchor_lang::Result<()> {
+        if !ctx.accounts.governance.config.supports_delegation() {
+            return Err(VotingErrorCode::DelegationNotSupported.into());
+        }
+
+        let user = &mut ctx.accounts.user;
+        let delegate = &mut ctx.accounts.delegate;
+        if user.key() == delegate.key()
+            || user.delegate.is_some()
+            || user.delegator_count > 0
+            || delegate.delegate.is_some()
+        {
+            return Err(VotingErrorCode::InvalidDelegate.into());
+        }
+
+        if user.active_votes > 0 {
+            return Err(VotingErrorCode::DelegationLocked.into());
+        }
+
+        user.delegate = Some(delegate.key());
+        delegate.delegator_count = delegate
+            .delegator_count
+            .checked_add(1)
+            .ok_or(VotingErrorCode::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Takes the voting power back from the delegate, once none of its vote
+    /// commitments can count it anymore.
+    pub fn undelegate(ctx: Context<Undelegate>) -> anchor_lang::Result<()> {
+        let delegate = &mut ctx.accounts.delegate;
+        if delegate.active_votes > 0 {
+            return Err(VotingErrorCode::DelegationLocked.into());
+        }
+
+        delegate.delegator_count = delegate
+            .delegator_count
+            .checked_sub(1)
+            .ok_or(VotingErrorCode::Overflow)?;
+        ctx.accounts.user.delegate = None;
+
+        Ok(())
+    }
+
+    pub fn deposit_governing_tokens(
+        ctx: Context<DepositGoverningTokens>,
+        amount: u64,
+    ) -> anchor_lang::Result<()> {
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let user = &mut ctx.accounts.user;
+        user.deposited_amount = user
+            .deposited_amount
+            .checked_add(amount)
+            .ok_or(VotingErrorCode::Overflow)?;
+
+        let governance = &mut ctx.accounts.governance;
+        governance.total_deposited = governance
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(VotingErrorCode::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Returns deposited community tokens to the member.
+    ///
+    /// Deposits stay locked while the member has a vote commitment that is not
+    /// closed, so the same tokens cannot vote twice from another wallet.
+    pub fn withdraw_governing_tokens(
+        ctx: Context<WithdrawGoverningTokens>,
+        amount: u64,
+    ) -> anchor_lang::Result<()> {
+        let user = &mut ctx.accounts.user;
+        if user.active_votes > 0 {
+            return Err(VotingErrorCode::TokensLocked.into());
+        }
+
+        if user.delegate.is_some() {
+            return Err(VotingErrorCode::VoteDelegated.into());
+        }
+
+        user.deposited_amount = user
+            .deposited_amount
+            .checked_sub(amount)
+            .ok_or(VotingErrorCode::InsufficientDeposit)?;
+
+        let governance = &mut ctx.accounts.governance;
+        governance.total_deposited = governance
+            .total_deposited
+            .checked_sub(amount)
+            .ok_or(VotingErrorCode::Overflow)?;
+
+        let bump = [ctx.bumps.governance];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            crate::constants::GOVERNANCE_SEED,
+            governance.name.as_bytes(),
+            &bump,
+        ]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: governance.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        Ok(())
+    }
+
+    /// Locks deposited community tokens until `now + duration` for voting
+    /// power in the vote escrow mode.
+    pub fn create_lockup(
+        ctx: Context<CreateLockup>,
+        amount: u64,
+        duration: i64,
+    ) -> anchor_lang::Result<()> {
+        let config = &ctx.accounts.governance.config;
+        if duration <= 0 || duration > config.max_lockup_period {
+            return Err(VotingErrorCode::InvalidLockupDuration.into());
+        }
+
+        let user = &mut ctx.accounts.user;
+        user.deposited_amount = user
+            .deposited_amount
+            .checked_sub(amount)
+            .ok_or(VotingErrorCode::InsufficientDeposit)?;
 
-        let _governance_key = governance_key;
+        let lockup = &mut ctx.accounts.lockup;
+        lockup.user = user.key();
+        lockup.amount = amount;
+        lockup.unlock_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(duration)
+            .ok_or(VotingErrorCode::Overflow)?;
 
         Ok(())
     }
 
+    /// Moves the unlock time of a lockup to `now + duration`, which can only
+    /// push it later.
+    pub fn extend_lockup(ctx: Context<ExtendLockup>, duration: i64) -> anchor_lang::Result<()> {
+        let config = &ctx.accounts.governance.config;
+        if duration <= 0 || duration > config.max_lockup_period {
+            return Err(VotingErrorCode::InvalidLockupDuration.into());
+        }
+
+        let unlock_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(duration)
+            .ok_or(VotingErrorCode::Overflow)?;
+
+        let lockup = &mut ctx.accounts.lockup;
+        if unlock_at <= lockup.unlock_at {
+            return Err(VotingErrorCode::InvalidLockupDuration.into());
+        }
+        lockup.unlock_at = unlock_at;
+
+        Ok(())
+    }
+
+    /// Returns the tokens of an expired lockup to the member deposit.
+    pub fn unlock(ctx: Context<Unlock>) -> anchor_lang::Result<()> {
+        let lockup = &ctx.accounts.lockup;
+        if lockup.unlock_at > Clock::get()?.unix_timestamp {
+            return Err(VotingErrorCode::LockupNotExpired.into());
+        }
+
+        let user = &mut ctx.accounts.user;
+        user.deposited_amount = user
+            .deposited_amount
+            .checked_add(lockup.amount)
+            .ok_or(VotingErrorCode::Overflow)?;
+
+        Ok(())
+    }
+
+    /// `chamber` is the chamber voting on the proposal, a governance without
+    /// a council chamber only has the community one.
     pub fn create_proposal(
         ctx: Context<CreateProposal>,
-        governance_key: Pubkey,
         title: Vec<u8>,
+        options: Vec<String>,
+        chamber: Chamber,
     ) -> anchor_lang::Result<()> {
+        if options.len() < 2
+            || options.len() > MAX_OPTIONS
+            || options
+                .iter()
+                .any(|option| option.is_empty() || option.len() > MAX_OPTION_LABEL_LEN)
+        {
+            return Err(VotingErrorCode::InvalidOptions.into());
+        }
+
+        if chamber.includes_council() && ctx.accounts.governance.config.council_chamber.is_none() {
+            return Err(VotingErrorCode::InvalidChamber.into());
+        }
+
         let proposal = &mut ctx.accounts.proposal;
-        proposal.governance = governance_key;
+        proposal.governance = ctx.accounts.governance.key();
+        proposal.creator = ctx.accounts.user.key();
         proposal.title = String::from_utf8(title).unwrap();
-        proposal.votes_for = 0;
-        proposal.votes_against = 0;
+        proposal.tallies = vec![0; options.len()];
+        proposal.council_tallies = vec![0; options.len()];
+        proposal.options = options;
+        proposal.votes_abstain = 0;
+        proposal.council_votes_abstain = 0;
+        proposal.chamber = chamber;
+        proposal.winning_option = None;
         proposal.start = 0;
         proposal.end = 0;
+        proposal.reveal_end = 0;
+        proposal.max_vote_weight = 0;
+        proposal.council_max_vote_weight = 0;
+        proposal.state = ProposalState::Draft;
+        proposal.succeeded_at = 0;
+        proposal.executable_at = 0;
+        proposal.expires_at = None;
+        proposal.transaction_counts = vec![0; proposal.options.len()];
+        proposal.executed_transactions = 0;
+        proposal.config_change_option = None;
+        proposal.config_change_applied = false;
 
         Ok(())
     }
 
     pub fn start_vote(ctx: Context<StartVote>, end: i64) -> anchor_lang::Result<()> {
+        let governance = &ctx.accounts.governance;
         let proposal = &mut ctx.accounts.proposal;
+        let authority = ctx.accounts.authority.key();
+
+        if authority != proposal.creator && authority != governance.authority {
+            return Err(VotingErrorCode::Unauthorized.into());
+        }
+
+        if proposal.state != ProposalState::Draft {
+            return Err(VotingErrorCode::VotingAlreadyStarted.into());
+        }
 
         let clock = Clock::get()?;
+        if end <= clock.unix_timestamp {
+            return Err(VotingErrorCode::VotingEndInPast.into());
+        }
+
+        let duration = end - clock.unix_timestamp;
+        if duration < governance.config.min_voting_period {
+            return Err(VotingErrorCode::VotingPeriodTooShort.into());
+        }
+
+        if duration > governance.config.max_voting_period {
+            return Err(VotingErrorCode::VotingPeriodTooLong.into());
+        }
+
         proposal.start = clock.unix_timestamp;
         proposal.end = end;
+        proposal.reveal_end = end
+            .checked_add(governance.config.reveal_period)
+            .ok_or(VotingErrorCode::InvalidGovernanceConfig)?;
+        proposal.max_vote_weight = governance.max_vote_weight();
+        proposal.council_max_vote_weight = match governance.config.council_chamber {
+            _ if !proposal.chamber.includes_council() => 0,
+            Some(CouncilMembership::Members) => governance.council_size,
+            Some(CouncilMembership::Mint(mint)) => {
+                ctx.accounts
+                    .council_mint
+                    .as_ref()
+                    .filter(|council_mint| council_mint.key() == mint)
+                    .ok_or(VotingErrorCode::InvalidMint)?
+                    .supply
+            }
+            None => return Err(VotingErrorCode::InvalidChamber.into()),
+        };
+        proposal.state = ProposalState::Voting;
 
         Ok(())
     }
 
-    pub fn commit_vote(ctx: Context<CommitVote>, commitment: String) -> anchor_lang::Result<()> {
+    pub fn commit_vote(ctx: Context<CommitVote>, commitment: [u8; 32]) -> anchor_lang::Result<()> {
         let proposal = &ctx.accounts.proposal;
+        if proposal.state != ProposalState::Voting {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
 
         let current_time = Clock::get()?.unix_timestamp;
         if proposal.start > current_time {
             return Err(VotingErrorCode::NotStarted.into());
         }
 
-        if proposal.end < current_time {
+        if proposal.end <= current_time {
             return Err(VotingErrorCode::VotingEnded.into());
         }
 
-        let vote_commitment = &mut ctx.accounts.vote_commitment;
+        if !proposal.chamber.includes_community() {
+            return Err(VotingErrorCode::InvalidChamber.into());
+        }
 
+        if ctx.accounts.member.delegate.is_some() {
+            return Err(VotingErrorCode::VoteDelegated.into());
+        }
+
+        let vote_commitment = &mut ctx.accounts.vote_commitment;
+        vote_commitment.proposal = proposal.key();
+        vote_commitment.voter = ctx.accounts.user.key();
         vote_commitment.commitment = commitment;
 
+        let member = &mut ctx.accounts.member;
+        member.active_votes = member
+            .active_votes
+            .checked_add(1)
+            .ok_or(VotingErrorCode::Overflow)?;
+
         Ok(())
     }
 
+    /// `credits` are the voice credits spent on the vote in the quadratic
+    /// voting mode and are ignored by the other modes.
+    ///
+    /// The `User` accounts of the members delegating to the voter are passed
+    /// as remaining accounts, their voting power is added to the vote.
+    ///
+    /// With a voter weight plugin, the weight comes from the plugin record
+    /// instead and delegation is not supported.
     pub fn reveal_vote(
         ctx: Context<RevealVote>,
-        vote: bool,
-        salt: String,
+        choice: VoteChoice,
+        credits: u64,
+        salt: [u8; 32],
     ) -> anchor_lang::Result<()> {
+        let governance = &ctx.accounts.governance;
         let proposal = &mut ctx.accounts.proposal;
         let user = &mut ctx.accounts.user;
 
@@ -97,156 +626,2295 @@ pub mod voting {
             return Err(VotingErrorCode::VotingNotEnded.into());
         }
 
-        let vote_commitment = &ctx.accounts.vote_commitment;
-        let temp = format!("{}{}", vote, salt);
-        let hash = solana_program::hash::hash(temp.as_bytes());
-        if vote_commitment.commitment != hash.to_string() {
-            return Err(VotingErrorCode::InvalidCommitment.into());
+        if proposal.reveal_end < clock.unix_timestamp {
+            return Err(VotingErrorCode::RevealEnded.into());
         }
 
-        if vote {
-            proposal.votes_for += 1;
-        } else {
-            proposal.votes_against += 1;
+        if proposal.current_state(clock.unix_timestamp) != ProposalState::Revealing {
+            return Err(VotingErrorCode::InvalidProposalState.into());
         }
+        proposal.state = ProposalState::Revealing;
 
-        user.points += 1;
+        let vote_commitment = &mut ctx.accounts.vote_commitment;
+        if vote_commitment.revealed {
+            return Err(VotingErrorCode::AlreadyRevealed.into());
+        }
 
-        Ok(())
-    }
-}
+        let hash = commitment::hash_vote(
+            &proposal.key(),
+            &vote_commitment.voter,
+            choice,
+            credits,
+            &salt,
+        );
+        if vote_commitment.commitment != hash {
+            return Err(VotingErrorCode::InvalidCommitment.into());
+        }
 
-#[derive(Accounts)]
-#[instruction(name: String)]
-pub struct CreateGovernance<'info> {
-    #[account(
-        init,
-        seeds = [crate::constants::GOVERNANCE_SEED, name.as_str().as_ref()],
-        bump,
-        payer = authority,
-        space = 8 + std::mem::size_of::<Governance>()
-    )]
-    pub governance: Account<'info, Governance>,
+        vote_commitment.revealed = true;
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        let mut weight = match governance.config.voter_weight_plugin {
+            Some(plugin) => {
+                let info = ctx
+                    .accounts
+                    .voter_weight_record
+                    .as_ref()
+                    .ok_or(VotingErrorCode::InvalidVoterWeightRecord)?;
+                if info.owner != &plugin {
+                    return Err(VotingErrorCode::InvalidVoterWeightRecord.into());
+                }
 
-    pub system_program: Program<'info, System>,
-}
+                let record = VoterWeightRecord::try_deserialize(&mut &info.data.borrow()[..])?;
+                if record.governance != governance.key() || record.voter != ctx.accounts.voter.key()
+                {
+                    return Err(VotingErrorCode::InvalidVoterWeightRecord.into());
+                }
 
-#[derive(Accounts)]
-#[instruction(governance_key: Pubkey)]
-pub struct Join<'info> {
-    #[account(
-        init,
-        seeds = [crate::constants::USER_SEED, governance_key.as_ref(), authority.key().as_ref()],
-        bump,
-        payer = authority,
-        space = 8 + std::mem::size_of::<User>()
-    )]
-    pub user: Account<'info, User>,
+                if record
+                    .voter_weight_expiry
+                    .is_some_and(|expiry| expiry < clock.slot)
+                {
+                    return Err(VotingErrorCode::VoterWeightRecordExpired.into());
+                }
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+                if !ctx.remaining_accounts.is_empty() {
+                    return Err(VotingErrorCode::InvalidDelegate.into());
+                }
 
-    pub system_program: Program<'info, System>,
-}
+                record.voter_weight
+            }
+            None if governance.config.voting_mode == VotingMode::NftWeighted => {
+                vote_commitment.nft_votes
+            }
+            None => {
+                let weight = user.vote_weight(
+                    &governance.config,
+                    credits,
+                    ctx.accounts.lockup.as_deref(),
+                    clock.unix_timestamp,
+                )?;
+                if governance.config.voting_mode == VotingMode::Quadratic {
+                    user.credits_spent = user
+                        .credits_spent
+                        .checked_add(credits)
+                        .ok_or(VotingErrorCode::Overflow)?;
+                    vote_commitment.credits = credits;
+                }
+                weight
+            }
+        };
 
-#[derive(Accounts)]
-#[instruction(governance_key: Pubkey, title: String)]
-pub struct CreateProposal<'info> {
-    #[account(
-        init,
-        seeds = [crate::constants::PROPOSAL_SEED, governance_key.as_ref(), title.as_str().as_ref()],
-        bump,
-        payer = user,
-        space = 8 + 32 + 4 + title.len() + 4 + 4 + 8 + 8
-    )]
-    pub proposal: Account<'info, Proposal>,
+        let mut delegators: Vec<Pubkey> = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts {
+            if info.owner != &crate::ID || delegators.contains(info.key) {
+                return Err(VotingErrorCode::InvalidDelegate.into());
+            }
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+            let delegator = User::try_deserialize(&mut &info.data.borrow()[..])?;
+            if delegator.governance != governance.key() || delegator.delegate != Some(user.key()) {
+                return Err(VotingErrorCode::InvalidDelegate.into());
+            }
 
-    pub system_program: Program<'info, System>,
-}
+            // Lockups are not delegated, and neither are voice credits
+            let delegated_weight =
+                delegator.vote_weight(&governance.config, 0, None, clock.unix_timestamp)?;
+            weight = weight
+                .checked_add(delegated_weight)
+                .ok_or(VotingErrorCode::Overflow)?;
+            delegators.push(info.key());
+        }
 
-#[derive(Accounts)]
-pub struct StartVote<'info> {
-    #[account(mut)]
-    pub proposal: Account<'info, Proposal>,
-}
+        if weight == 0 {
+            return Err(VotingErrorCode::NoVotingPower.into());
+        }
 
-#[derive(Accounts)]
-pub struct CommitVote<'info> {
-    #[account(mut)]
-    pub governance: Account<'info, Governance>,
+        let tally = match choice {
+            VoteChoice::Option(index) => proposal
+                .tallies
+                .get_mut(index as usize)
+                .ok_or(VotingErrorCode::InvalidOptions)?,
+            VoteChoice::Abstain => &mut proposal.votes_abstain,
+        };
+        *tally = tally.checked_add(weight).ok_or(VotingErrorCode::Overflow)?;
 
-    #[account(mut)]
-    pub proposal: Account<'info, Proposal>,
+        user.points = user
+            .points
+            .checked_add(1)
+            .ok_or(VotingErrorCode::Overflow)?;
 
-    #[account(
-        init,
-        seeds = [crate::constants::COMMIT_VOTE_SEED, governance.key().as_ref(), proposal.key().as_ref(), user.key().as_ref()],
-        bump,
-        payer = user,
-        space = 8 + std::mem::size_of::<Proposal>()
-    )]
-    pub vote_commitment: Account<'info, VoteCommitment>,
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+    /// Counts an NFT of the governance collection toward the vote of the
+    /// signer in the NFT voting mode. The NFT vote record makes sure an NFT is
+    /// counted once per proposal, whoever holds it.
+    pub fn register_nft_vote(ctx: Context<RegisterNftVote>) -> anchor_lang::Result<()> {
+        let collection = ctx
+            .accounts
+            .governance
+            .config
+            .nft_collection
+            .ok_or(VotingErrorCode::InvalidNft)?;
+        verify_nft(
+            &collection,
+            &ctx.accounts.voter.key(),
+            &ctx.accounts.nft_token_account,
+            &ctx.accounts.nft_metadata,
+        )?;
 
-    pub system_program: Program<'info, System>,
-}
+        let proposal = &ctx.accounts.proposal;
+        let current_time = Clock::get()?.unix_timestamp;
+        if !matches!(
+            proposal.current_state(current_time),
+            ProposalState::Voting | ProposalState::Revealing
+        ) {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
 
-#[derive(Accounts)]
-pub struct RevealVote<'info> {
-    #[account(mut)]
-    pub proposal: Account<'info, Proposal>,
+        if proposal.reveal_end < current_time {
+            return Err(VotingErrorCode::RevealEnded.into());
+        }
 
-    #[account(mut)]
-    pub vote_commitment: Account<'info, VoteCommitment>,
+        let vote_commitment = &mut ctx.accounts.vote_commitment;
+        if vote_commitment.revealed {
+            return Err(VotingErrorCode::AlreadyRevealed.into());
+        }
 
-    #[account(mut)]
-    pub user: Account<'info, User>,
-}
+        vote_commitment.nft_votes = vote_commitment
+            .nft_votes
+            .checked_add(1)
+            .ok_or(VotingErrorCode::Overflow)?;
 
-#[account]
-pub struct Governance {
-    name: String,
-}
+        let nft_vote_record = &mut ctx.accounts.nft_vote_record;
+        nft_vote_record.proposal = proposal.key();
+        nft_vote_record.mint = ctx.accounts.nft_token_account.mint;
+        nft_vote_record.voter = ctx.accounts.voter.key();
 
-#[account]
-pub struct Proposal {
-    governance: Pubkey,
-    title: String,
-    pub votes_for: u32,
-    pub votes_against: u32,
-    start: i64,
-    end: i64,
-}
+        Ok(())
+    }
 
-#[account]
-pub struct VoteCommitment {
-    commitment: String,
-}
+    /// Lists the members of a council chamber made of a fixed list of
+    /// wallets, once, while the governance authority can update the config.
+    pub fn create_council(
+        ctx: Context<CreateCouncil>,
+        members: Vec<Pubkey>,
+    ) -> anchor_lang::Result<()> {
+        let governance = &mut ctx.accounts.governance;
+        if !governance.config.allow_authority_updates {
+            return Err(VotingErrorCode::AuthorityUpdatesDisabled.into());
+        }
 
-#[account]
-pub struct User {
-    points: u32,
-}
+        if governance.config.council_chamber != Some(CouncilMembership::Members) {
+            return Err(VotingErrorCode::InvalidChamber.into());
+        }
 
-#[error_code]
-pub enum VotingErrorCode {
-    #[msg("Voting has not started yet")]
-    NotStarted,
+        if members.is_empty()
+            || members.len() > MAX_COUNCIL_MEMBERS
+            || members
+                .iter()
+                .enumerate()
+                .any(|(index, member)| members[..index].contains(member))
+        {
+            return Err(VotingErrorCode::InvalidCouncil.into());
+        }
 
-    #[msg("Voting ended")]
-    VotingEnded,
+        governance.council_size = members.len() as u64;
 
-    #[msg("Voting not ended")]
-    VotingNotEnded,
+        let council = &mut ctx.accounts.council;
+        council.governance = governance.key();
+        council.members = members;
 
-    #[msg("Invalid Commitment")]
-    InvalidCommitment,
+        Ok(())
+    }
+
+    /// Commits the vote of a council member. A council of mint holders
+    /// escrows `amount` council tokens on the vote until it is closed, they
+    /// are the weight of the vote.
+    pub fn commit_council_vote(
+        ctx: Context<CommitCouncilVote>,
+        commitment: [u8; 32],
+        amount: u64,
+    ) -> anchor_lang::Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        if proposal.state != ProposalState::Voting {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+
+        let current_time = Clock::get()?.unix_timestamp;
+        if proposal.start > current_time {
+            return Err(VotingErrorCode::NotStarted.into());
+        }
+
+        if proposal.end <= current_time {
+            return Err(VotingErrorCode::VotingEnded.into());
+        }
+
+        if !proposal.chamber.includes_council() {
+            return Err(VotingErrorCode::InvalidChamber.into());
+        }
+
+        let voter = ctx.accounts.voter.key();
+        match ctx.accounts.governance.config.council_chamber {
+            Some(CouncilMembership::Members) => {
+                let is_member = ctx
+                    .accounts
+                    .council
+                    .as_ref()
+                    .is_some_and(|council| council.members.contains(&voter));
+                if !is_member {
+                    return Err(VotingErrorCode::NotACouncilMember.into());
+                }
+            }
+            Some(CouncilMembership::Mint(mint)) => {
+                let (Some(council_mint), Some(source), Some(escrow)) = (
+                    &ctx.accounts.council_mint,
+                    &ctx.accounts.source,
+                    &ctx.accounts.escrow,
+                ) else {
+                    return Err(VotingErrorCode::InvalidMint.into());
+                };
+                if council_mint.key() != mint {
+                    return Err(VotingErrorCode::InvalidMint.into());
+                }
+
+                if amount == 0 {
+                    return Err(VotingErrorCode::NoVotingPower.into());
+                }
+
+                token::transfer(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: source.to_account_info(),
+                            to: escrow.to_account_info(),
+                            authority: ctx.accounts.voter.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )?;
+            }
+            None => return Err(VotingErrorCode::InvalidChamber.into()),
+        }
+
+        let vote_commitment = &mut ctx.accounts.vote_commitment;
+        vote_commitment.proposal = proposal.key();
+        vote_commitment.voter = voter;
+        vote_commitment.commitment = commitment;
+
+        Ok(())
+    }
+
+    /// Reveals the vote of a council member into the council tallies.
+    pub fn reveal_council_vote(
+        ctx: Context<RevealCouncilVote>,
+        choice: VoteChoice,
+        salt: [u8; 32],
+    ) -> anchor_lang::Result<()> {
+        let governance = &ctx.accounts.governance;
+        let proposal = &mut ctx.accounts.proposal;
+
+        let clock = Clock::get()?;
+        if proposal.end > clock.unix_timestamp {
+            return Err(VotingErrorCode::VotingNotEnded.into());
+        }
+
+        if proposal.reveal_end < clock.unix_timestamp {
+            return Err(VotingErrorCode::RevealEnded.into());
+        }
+
+        if proposal.current_state(clock.unix_timestamp) != ProposalState::Revealing {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+        proposal.state = ProposalState::Revealing;
+
+        let vote_commitment = &mut ctx.accounts.vote_commitment;
+        if vote_commitment.revealed {
+            return Err(VotingErrorCode::AlreadyRevealed.into());
+        }
+
+        let hash = commitment::hash_vote(&proposal.key(), &vote_commitment.voter, choice, 0, &salt);
+        if vote_commitment.commitment != hash {
+            return Err(VotingErrorCode::InvalidCommitment.into());
+        }
+
+        vote_commitment.revealed = true;
+
+        let weight = match governance.config.council_chamber {
+            Some(CouncilMembership::Members) => 1,
+            Some(CouncilMembership::Mint(_)) => {
+                ctx.accounts
+                    .escrow
+                    .as_ref()
+                    .ok_or(VotingErrorCode::InvalidMint)?
+                    .amount
+            }
+            None => return Err(VotingErrorCode::InvalidChamber.into()),
+        };
+        if weight == 0 {
+            return Err(VotingErrorCode::NoVotingPower.into());
+        }
+
+        let tally = match choice {
+            VoteChoice::Option(index) => proposal
+                .council_tallies
+                .get_mut(index as usize)
+                .ok_or(VotingErrorCode::InvalidOptions)?,
+            VoteChoice::Abstain => &mut proposal.council_votes_abstain,
+        };
+        *tally = tally.checked_add(weight).ok_or(VotingErrorCode::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Closes the council vote commitment of a finalized proposal, returning
+    /// the escrowed council tokens to `destination`.
+    pub fn close_council_vote(ctx: Context<CloseCouncilVote>) -> anchor_lang::Result<()> {
+        if !ctx.accounts.proposal.state.is_final() {
+            return Err(VotingErrorCode::ProposalNotFinalized.into());
+        }
+
+        // The escrow of a mint council can only be closed with its commitment
+        let escrow = match (
+            &ctx.accounts.escrow,
+            ctx.accounts.governance.config.council_chamber,
+        ) {
+            (Some(escrow), _) => escrow,
+            (None, Some(CouncilMembership::Mint(_))) => {
+                return Err(VotingErrorCode::InvalidMint.into())
+            }
+            (None, _) => return Ok(()),
+        };
+        let destination = ctx
+            .accounts
+            .destination
+            .as_ref()
+            .filter(|destination| destination.mint == escrow.mint)
+            .ok_or(VotingErrorCode::InvalidMint)?;
+
+        let governance = &ctx.accounts.governance;
+        let bump = [ctx.bumps.governance];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            crate::constants::GOVERNANCE_SEED,
+            governance.name.as_bytes(),
+            &bump,
+        ]];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: escrow.to_account_info(),
+                    to: destination.to_account_info(),
+                    authority: governance.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            escrow.amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: escrow.to_account_info(),
+                destination: ctx.accounts.voter.to_account_info(),
+                authority: governance.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        Ok(())
+    }
+
+    pub fn finalize_proposal(ctx: Context<FinalizeProposal>) -> anchor_lang::Result<()> {
+        let governance = &ctx.accounts.governance;
+        let proposal = &mut ctx.accounts.proposal;
+
+        // Revealing also when no vote was revealed
+        let clock = Clock::get()?;
+        if proposal.current_state(clock.unix_timestamp) != ProposalState::Revealing {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+
+        if proposal.reveal_end >= clock.unix_timestamp {
+            return Err(VotingErrorCode::RevealNotEnded.into());
+        }
+
+        let community = governance.config.winning_option(
+            proposal.max_vote_weight,
+            &proposal.tallies,
+            proposal.votes_abstain,
+        );
+        let council = governance.config.council_winning_option(
+            proposal.council_max_vote_weight,
+            &proposal.council_tallies,
+            proposal.council_votes_abstain,
+        );
+        proposal.winning_option = proposal.chamber.decide(community, council);
+        proposal.state = if proposal.winning_option.is_some() {
+            // Later config updates do not move the execution window
+            (proposal.executable_at, proposal.expires_at) =
+                governance.config.execution_window(clock.unix_timestamp)?;
+            proposal.succeeded_at = clock.unix_timestamp;
+            ProposalState::Succeeded
+        } else {
+            ProposalState::Defeated
+        };
+
+        Ok(())
+    }
+
+    /// Moves a succeeded proposal to `Expired` once its grace period ended
+    /// unexecuted.
+    pub fn expire_proposal(ctx: Context<ExpireProposal>) -> anchor_lang::Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        if proposal.current_state(Clock::get()?.unix_timestamp) != ProposalState::Expired {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+
+        proposal.state = ProposalState::Expired;
+
+        Ok(())
+    }
+
+    /// Withdraws a proposal, by its creator while it is a draft or its voting
+    /// period is running.
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> anchor_lang::Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        if ctx.accounts.authority.key() != proposal.creator {
+            return Err(VotingErrorCode::Unauthorized.into());
+        }
+
+        if !matches!(
+            proposal.current_state(Clock::get()?.unix_timestamp),
+            ProposalState::Draft | ProposalState::Voting
+        ) {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+
+        proposal.state = ProposalState::Cancelled;
+
+        emit!(ProposalCancelled {
+            governance: ctx.accounts.governance.key(),
+            proposal: proposal.key(),
+            creator: proposal.creator,
+        });
+
+        Ok(())
+    }
+
+    /// Cancels any proposal whose execution has not started, by the
+    /// governance council.
+    pub fn veto_proposal(ctx: Context<VetoProposal>) -> anchor_lang::Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        if !matches!(
+            proposal.current_state(Clock::get()?.unix_timestamp),
+            ProposalState::Draft
+                | ProposalState::Voting
+                | ProposalState::Revealing
+                | ProposalState::Succeeded
+        ) || proposal.executed_transactions > 0
+            || proposal.config_change_applied
+        {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+
+        proposal.state = ProposalState::Cancelled;
+
+        emit!(ProposalVetoed {
+            governance: ctx.accounts.governance.key(),
+            proposal: proposal.key(),
+            council: ctx.accounts.council.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Attaches a transaction to an option of a draft proposal, executed if
+    /// the option wins. `index` is the number of transactions the option
+    /// already has, transactions execute in that order.
+    pub fn insert_transaction(
+        ctx: Context<InsertTransaction>,
+        option: u8,
+        index: u16,
+        instructions: Vec<ProposalInstruction>,
+    ) -> anchor_lang::Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        if ctx.accounts.authority.key() != proposal.creator {
+            return Err(VotingErrorCode::Unauthorized.into());
+        }
+
+        if proposal.state != ProposalState::Draft {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+
+        // The voting program cannot be invoked, its accounts would be written
+        // back over by the outer instruction
+        if proposal.transaction_counts.get(option as usize) != Some(&index)
+            || instructions.is_empty()
+            || instructions
+                .iter()
+                .any(|instruction| instruction.program_id == crate::ID)
+        {
+            return Err(VotingErrorCode::InvalidTransaction.into());
+        }
+
+        proposal.transaction_counts[option as usize] =
+            index.checked_add(1).ok_or(VotingErrorCode::Overflow)?;
+
+        let transaction = &mut ctx.accounts.proposal_transaction;
+        transaction.proposal = proposal.key();
+        transaction.option = option;
+        transaction.index = index;
+        transaction.instructions = instructions;
+        transaction.executed = false;
+
+        Ok(())
+    }
+
+    /// Invokes the instructions of the next transaction of the winning option,
+    /// signed by the treasury PDA.
+    ///
+    /// The accounts of the instructions, and their programs, are passed as
+    /// remaining accounts.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> anchor_lang::Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        let transaction = &mut ctx.accounts.proposal_transaction;
+
+        if proposal.state != ProposalState::Succeeded {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+
+        if transaction.executed {
+            return Err(VotingErrorCode::TransactionAlreadyExecuted.into());
+        }
+
+        proposal.check_execution(Clock::get()?.unix_timestamp)?;
+
+        if proposal.winning_option != Some(transaction.option)
+            || transaction.index != proposal.executed_transactions
+        {
+            return Err(VotingErrorCode::InvalidTransaction.into());
+        }
+
+        let governance = ctx.accounts.governance.key();
+        let signer_seeds: &[&[u8]] = &[
+            crate::constants::TREASURY_SEED,
+            governance.as_ref(),
+            &[ctx.bumps.treasury],
+        ];
+        for instruction in &transaction.instructions {
+            invoke_signed(&instruction.into(), ctx.remaining_accounts, &[signer_seeds])?;
+        }
+
+        transaction.executed = true;
+        proposal.executed_transactions = proposal
+            .executed_transactions
+            .checked_add(1)
+            .ok_or(VotingErrorCode::Overflow)?;
+        if !proposal.has_pending_execution() {
+            proposal.state = ProposalState::Executed;
+        }
+
+        Ok(())
+    }
+
+    /// Attaches a new governance config to an option of a draft proposal,
+    /// applied by `execute_config_change` if the option wins. A proposal
+    /// carries at most one config change.
+    pub fn insert_config_change(
+        ctx: Context<InsertConfigChange>,
+        option: u8,
+        config: GovernanceConfig,
+    ) -> anchor_lang::Result<()> {
+        let proposal = &mut ctx.accounts.proposal;
+        if ctx.accounts.authority.key() != proposal.creator {
+            return Err(VotingErrorCode::Unauthorized.into());
+        }
+
+        if proposal.state != ProposalState::Draft {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+
+        if option as usize >= proposal.options.len() {
+            return Err(VotingErrorCode::InvalidOptions.into());
+        }
+
+        // The council alone cannot change the rules
+        if !proposal.chamber.includes_community() {
+            return Err(VotingErrorCode::InvalidChamber.into());
+        }
+
+        ctx.accounts.governance.check_update(&config)?;
+
+        proposal.config_change_option = Some(option);
+
+        let config_change = &mut ctx.accounts.config_change;
+        config_change.proposal = proposal.key();
+        config_change.option = option;
+        config_change.config = config;
+
+        Ok(())
+    }
+
+    /// Replaces the governance config with the config change of the winning
+    /// option, once the execution timelock elapsed.
+    pub fn execute_config_change(ctx: Context<ExecuteConfigChange>) -> anchor_lang::Result<()> {
+        let governance = &mut ctx.accounts.governance;
+        let proposal = &mut ctx.accounts.proposal;
+        let config_change = &ctx.accounts.config_change;
+
+        if proposal.state != ProposalState::Succeeded {
+            return Err(VotingErrorCode::InvalidProposalState.into());
+        }
+
+        if proposal.config_change_option.is_none() {
+            return Err(VotingErrorCode::TransactionAlreadyExecuted.into());
+        }
+
+        proposal.check_execution(Clock::get()?.unix_timestamp)?;
+
+        if proposal.winning_option != Some(config_change.option) {
+            return Err(VotingErrorCode::InvalidTransaction.into());
+        }
+
+        // The config may have changed since the proposal was created
+        governance.check_update(&config_change.config)?;
+        governance.config = config_change.config.clone();
+
+        proposal.config_change_option = None;
+        proposal.config_change_applied = true;
+        if !proposal.has_pending_execution() {
+            proposal.state = ProposalState::Executed;
+        }
+
+        Ok(())
+    }
+
+    /// Refunds the commitment rent once the proposal is finalized, which also
+    /// releases its lock on the member deposit.
+    pub fn close_vote_commitment(ctx: Context<CloseVoteCommitment>) -> anchor_lang::Result<()> {
+        if !ctx.accounts.proposal.state.is_final() {
+            return Err(VotingErrorCode::ProposalNotFinalized.into());
+        }
+
+        let user = &mut ctx.accounts.user;
+        user.active_votes = user
+            .active_votes
+            .checked_sub(1)
+            .ok_or(VotingErrorCode::Overflow)?;
+        user.credits_spent = user
+            .credits_spent
+            .checked_sub(ctx.accounts.vote_commitment.credits)
+            .ok_or(VotingErrorCode::Overflow)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct CreateGovernance<'info> {
+    #[account(
+        init,
+        seeds = [crate::constants::GOVERNANCE_SEED, name.as_str().as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + 32 + 4 + name.len() + GovernanceConfig::LEN + 8 + 8 + 8 + 8
+    )]
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGovernanceConfig<'info> {
+    #[account(mut, has_one = authority @ VotingErrorCode::Unauthorized)]
+    pub governance: Account<'info, Governance>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Join<'info> {
+    #[account(mut)]
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + std::mem::size_of::<User>()
+    )]
+    pub user: Account<'info, User>,
+
+    /// NFT of the governance collection, when the governance has one
+    pub nft_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub nft_metadata: Option<Account<'info, MetadataAccount>>,
+
+    /// Record of the NFT backing the membership, an NFT backs one member
+    #[account(
+        init,
+        seeds = [crate::constants::MEMBER_NFT_SEED, governance.key().as_ref(), nft_token_account.as_ref().ok_or(VotingErrorCode::InvalidNft)?.mint.as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + 32 + 32 + 32
+    )]
+    pub member_nft_record: Option<Account<'info, MemberNftRecord>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Delegate<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump,
+        constraint = user.authority == authority.key() @ VotingErrorCode::NotAMember
+    )]
+    pub user: Account<'info, User>,
+
+    #[account(mut, has_one = governance @ VotingErrorCode::NotAMember)]
+    pub delegate: Account<'info, User>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Undelegate<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump,
+        constraint = user.authority == authority.key() @ VotingErrorCode::NotAMember,
+        constraint = user.delegate == Some(delegate.key()) @ VotingErrorCode::InvalidDelegate
+    )]
+    pub user: Account<'info, User>,
+
+    #[account(mut)]
+    pub delegate: Account<'info, User>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositGoverningTokens<'info> {
+    #[account(mut)]
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump,
+        constraint = user.authority == authority.key() @ VotingErrorCode::NotAMember
+    )]
+    pub user: Account<'info, User>,
+
+    #[account(
+        constraint = governance.config.community_mint == Some(community_mint.key()) @ VotingErrorCode::InvalidMint
+    )]
+    pub community_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        seeds = [crate::constants::VAULT_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump,
+        payer = authority,
+        token::mint = community_mint,
+        token::authority = governance
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = community_mint)]
+    pub source: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawGoverningTokens<'info> {
+    #[account(
+        mut,
+        seeds = [crate::constants::GOVERNANCE_SEED, governance.name.as_bytes()],
+        bump
+    )]
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump,
+        constraint = user.authority == authority.key() @ VotingErrorCode::NotAMember
+    )]
+    pub user: Account<'info, User>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::VAULT_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = vault.mint)]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateLockup<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump,
+        constraint = user.authority == authority.key() @ VotingErrorCode::NotAMember
+    )]
+    pub user: Account<'info, User>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::LOCKUP_SEED, user.key().as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + std::mem::size_of::<Lockup>()
+    )]
+    pub lockup: Account<'info, Lockup>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExtendLockup<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump,
+        constraint = user.authority == authority.key() @ VotingErrorCode::NotAMember
+    )]
+    pub user: Account<'info, User>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::LOCKUP_SEED, user.key().as_ref()],
+        bump,
+        has_one = user
+    )]
+    pub lockup: Account<'info, Lockup>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Unlock<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), authority.key().as_ref()],
+        bump,
+        constraint = user.authority == authority.key() @ VotingErrorCode::NotAMember
+    )]
+    pub user: Account<'info, User>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [crate::constants::LOCKUP_SEED, user.key().as_ref()],
+        bump,
+        has_one = user
+    )]
+    pub lockup: Account<'info, Lockup>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(title: String, options: Vec<String>)]
+pub struct CreateProposal<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = member.authority == user.key() @ VotingErrorCode::NotAMember
+    )]
+    pub member: Account<'info, User>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::PROPOSAL_SEED, governance.key().as_ref(), title.as_str().as_ref()],
+        bump,
+        payer = user,
+        space = Proposal::space(&title, &options)
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StartVote<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    /// Mint of a council of token holders, its supply is the council voting
+    /// power
+    pub council_mint: Option<Account<'info, Mint>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CommitVote<'info> {
+    #[account(mut)]
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), user.key().as_ref()],
+        bump,
+        constraint = member.authority == user.key() @ VotingErrorCode::NotAMember
+    )]
+    pub member: Account<'info, User>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::COMMIT_VOTE_SEED, governance.key().as_ref(), proposal.key().as_ref(), user.key().as_ref()],
+        bump,
+        payer = user,
+        space = 8 + 32 + 32 + 32 + 1 + 8 + 8
+    )]
+    pub vote_commitment: Account<'info, VoteCommitment>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealVote<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::COMMIT_VOTE_SEED, governance.key().as_ref(), proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        has_one = proposal @ VotingErrorCode::InvalidCommitment,
+        has_one = voter @ VotingErrorCode::InvalidCommitment
+    )]
+    pub vote_commitment: Account<'info, VoteCommitment>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::USER_SEED, governance.key().as_ref(), voter.key().as_ref()],
+        bump,
+        constraint = user.authority == voter.key() @ VotingErrorCode::NotAMember
+    )]
+    pub user: Account<'info, User>,
+
+    /// Voting power of the vote escrow mode
+    #[account(
+        seeds = [crate::constants::LOCKUP_SEED, user.key().as_ref()],
+        bump,
+        has_one = user
+    )]
+    pub lockup: Option<Account<'info, Lockup>>,
+
+    /// CHECK: owned by the governance voter weight plugin, validated in `reveal_vote`
+    pub voter_weight_record: Option<UncheckedAccount<'info>>,
+
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterNftVote<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::COMMIT_VOTE_SEED, governance.key().as_ref(), proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        has_one = voter @ VotingErrorCode::InvalidCommitment
+    )]
+    pub vote_commitment: Account<'info, VoteCommitment>,
+
+    pub nft_token_account: Account<'info, TokenAccount>,
+
+    pub nft_metadata: Account<'info, MetadataAccount>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::NFT_VOTE_SEED, proposal.key().as_ref(), nft_token_account.mint.as_ref()],
+        bump,
+        payer = voter,
+        space = 8 + std::mem::size_of::<NftVoteRecord>()
+    )]
+    pub nft_vote_record: Account<'info, NftVoteRecord>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireProposal<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VetoProposal<'info> {
+    #[account(constraint = governance.config.council == Some(council.key()) @ VotingErrorCode::Unauthorized)]
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    pub council: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(option: u8, index: u16, instructions: Vec<ProposalInstruction>)]
+pub struct InsertTransaction<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::PROPOSAL_TRANSACTION_SEED, proposal.key().as_ref(), &[option], &index.to_le_bytes()],
+        bump,
+        payer = authority,
+        space = ProposalTransaction::space(&instructions)
+    )]
+    pub proposal_transaction: Account<'info, ProposalTransaction>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(mut, has_one = proposal)]
+    pub proposal_transaction: Account<'info, ProposalTransaction>,
+
+    /// CHECK: Treasury of the governance, a system account holding SOL and
+    /// owning token accounts. Only signs the instructions of the proposal
+    #[account(
+        seeds = [crate::constants::TREASURY_SEED, governance.key().as_ref()],
+        bump
+    )]
+    pub treasury: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InsertConfigChange<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::CONFIG_CHANGE_SEED, proposal.key().as_ref()],
+        bump,
+        payer = authority,
+        space = 8 + 32 + 1 + GovernanceConfig::LEN
+    )]
+    pub config_change: Account<'info, ConfigChange>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteConfigChange<'info> {
+    #[account(mut)]
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        seeds = [crate::constants::CONFIG_CHANGE_SEED, proposal.key().as_ref()],
+        bump,
+        has_one = proposal
+    )]
+    pub config_change: Account<'info, ConfigChange>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVoteCommitment<'info> {
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        close = voter,
+        seeds = [crate::constants::COMMIT_VOTE_SEED, proposal.governance.as_ref(), proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        has_one = proposal @ VotingErrorCode::InvalidCommitment,
+        has_one = voter @ VotingErrorCode::InvalidCommitment
+    )]
+    pub vote_commitment: Account<'info, VoteCommitment>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::USER_SEED, proposal.governance.as_ref(), voter.key().as_ref()],
+        bump,
+        constraint = user.authority == voter.key() @ VotingErrorCode::NotAMember
+    )]
+    pub user: Account<'info, User>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateCouncil<'info> {
+    #[account(mut, has_one = authority @ VotingErrorCode::Unauthorized)]
+    pub governance: Account<'info, Governance>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::COUNCIL_SEED, governance.key().as_ref()],
+        bump,
+        payer = authority,
+        space = Council::space(crate::constants::MAX_COUNCIL_MEMBERS)
+    )]
+    pub council: Account<'info, Council>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitCouncilVote<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    /// Members of a council of wallets
+    #[account(
+        seeds = [crate::constants::COUNCIL_SEED, governance.key().as_ref()],
+        bump,
+        has_one = governance
+    )]
+    pub council: Option<Account<'info, Council>>,
+
+    #[account(
+        init,
+        seeds = [crate::constants::COUNCIL_VOTE_SEED, proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        payer = voter,
+        space = 8 + 32 + 32 + 32 + 1 + 8 + 8
+    )]
+    pub vote_commitment: Account<'info, VoteCommitment>,
+
+    /// Mint of a council of token holders
+    pub council_mint: Option<Account<'info, Mint>>,
+
+    #[account(mut)]
+    pub source: Option<Account<'info, TokenAccount>>,
+
+    /// Council tokens escrowed on the vote
+    #[account(
+        init,
+        seeds = [crate::constants::COUNCIL_ESCROW_SEED, proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        payer = voter,
+        token::mint = council_mint,
+        token::authority = governance
+    )]
+    pub escrow: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealCouncilVote<'info> {
+    pub governance: Account<'info, Governance>,
+
+    #[account(mut, has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::COUNCIL_VOTE_SEED, proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        has_one = proposal @ VotingErrorCode::InvalidCommitment,
+        has_one = voter @ VotingErrorCode::InvalidCommitment
+    )]
+    pub vote_commitment: Account<'info, VoteCommitment>,
+
+    #[account(
+        seeds = [crate::constants::COUNCIL_ESCROW_SEED, proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<Account<'info, TokenAccount>>,
+
+    pub voter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCouncilVote<'info> {
+    #[account(
+        seeds = [crate::constants::GOVERNANCE_SEED, governance.name.as_bytes()],
+        bump
+    )]
+    pub governance: Account<'info, Governance>,
+
+    #[account(has_one = governance)]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        close = voter,
+        seeds = [crate::constants::COUNCIL_VOTE_SEED, proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+        has_one = proposal @ VotingErrorCode::InvalidCommitment,
+        has_one = voter @ VotingErrorCode::InvalidCommitment
+    )]
+    pub vote_commitment: Account<'info, VoteCommitment>,
+
+    #[account(
+        mut,
+        seeds = [crate::constants::COUNCIL_ESCROW_SEED, proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub destination: Option<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub voter: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// Members of a council chamber of wallets.
+#[account]
+pub struct Council {
+    pub governance: Pubkey,
+    pub members: Vec<Pubkey>,
+}
+
+impl Council {
+    pub fn space(max_members: usize) -> usize {
+        8 + 32 + 4 + 32 * max_members
+    }
+}
+
+#[account]
+pub struct Governance {
+    pub authority: Pubkey,
+    name: String,
+    pub config: GovernanceConfig,
+    pub member_count: u64,
+    pub total_deposited: u64,
+    /// Sum of the membership weights of the members
+    pub total_membership_weight: u64,
+    /// Members of the `Council` account
+    pub council_size: u64,
+}
+
+impl Governance {
+    /// Checks the governance config can be replaced with `config`: it is
+    /// valid, keeps the community mint once set, and does not add a council
+    /// of members no one can list anymore.
+    pub fn check_update(&self, config: &GovernanceConfig) -> anchor_lang::Result<()> {
+        config.validate()?;
+
+        if self.config.community_mint.is_some()
+            && self.config.community_mint != config.community_mint
+        {
+            return Err(VotingErrorCode::InvalidGovernanceConfig.into());
+        }
+
+        // Only the governance authority lists the council members
+        if config.council_chamber == Some(CouncilMembership::Members)
+            && self.council_size == 0
+            && !config.allow_authority_updates
+        {
+            return Err(VotingErrorCode::InvalidChamber.into());
+        }
+
+        Ok(())
+    }
+
+    /// Total weight the votes on a proposal can add up to.
+    pub fn max_vote_weight(&self) -> u64 {
+        match self.config.voting_mode {
+            VotingMode::OneMemberOneVote => self.total_membership_weight,
+            VotingMode::TokenWeighted | VotingMode::VoteEscrow => self.total_deposited,
+            // Unknown, NFT governances use an absolute quorum
+            VotingMode::NftWeighted => 0,
+            // Upper bound of the sum of the square roots of the deposits
+            VotingMode::Quadratic => {
+                integer_sqrt(self.member_count as u128 * self.total_deposited as u128)
+            }
+        }
+    }
+}
+
+/// Rules every proposal of a governance is held to.
+///
+/// Periods are in seconds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GovernanceConfig {
+    pub min_voting_period: i64,
+    pub max_voting_period: i64,
+    pub reveal_period: i64,
+    pub quorum: Quorum,
+    pub approval_threshold: ApprovalThreshold,
+    pub voting_mode: VotingMode,
+    pub community_mint: Option<Pubkey>,
+    /// Lockup duration giving the full voting power in the vote escrow mode
+    pub max_lockup_period: i64,
+    /// Program producing the `VoterWeightRecord` of voters, overrides the voting mode
+    pub voter_weight_plugin: Option<Pubkey>,
+    /// Verified collection of the NFTs gating membership
+    pub nft_collection: Option<Pubkey>,
+    /// Merkle root of the wallets allowed to join and their weights, see `merkle`
+    pub membership_root: Option<[u8; 32]>,
+    /// Timelock between the success of a proposal and its execution
+    pub execution_delay: i64,
+    /// Time after the timelock a proposal can be executed in, without limit when `None`
+    pub execution_grace_period: Option<i64>,
+    /// Council key holding a veto over proposals until their execution starts
+    pub council: Option<Pubkey>,
+    /// Second chamber voting on the proposals addressed to the council
+    pub council_chamber: Option<CouncilMembership>,
+    /// Quorum of the council chamber, in council votes
+    pub council_quorum: Quorum,
+    pub council_approval_threshold: ApprovalThreshold,
+    /// The governance authority can update the config, otherwise only config
+    /// change proposals can
+    pub allow_authority_updates: bool,
+}
+
+impl GovernanceConfig {
+    pub const LEN: usize = 8
+        + 8
+        + 8
+        + Quorum::LEN
+        + ApprovalThreshold::LEN
+        + VotingMode::LEN
+        + 1
+        + 32
+        + 8
+        + 1
+        + 32
+        + 1
+        + 32
+        + 1
+        + 32
+        + 8
+        + 1
+        + 8
+        + 1
+        + 32
+        + 1
+        + CouncilMembership::LEN
+        + Quorum::LEN
+        + ApprovalThreshold::LEN
+        + 1;
+
+    pub fn validate(&self) -> anchor_lang::Result<()> {
+        if self.min_voting_period <= 0
+            || self.min_voting_period > self.max_voting_period
+            || self.reveal_period <= 0
+            || self.max_lockup_period <= 0
+            || self.execution_delay < 0
+            || self.execution_grace_period.is_some_and(|grace_period| grace_period <= 0)
+            || !self.quorum.is_valid()
+            || !self.approval_threshold.is_valid()
+            || !self.council_quorum.is_valid()
+            || !self.council_approval_threshold.is_valid()
+            || (self.voting_mode.requires_community_mint() && self.community_mint.is_none())
+            || (self.voting_mode == VotingMode::NftWeighted && self.nft_collection.is_none())
+            // The maximum vote weight of a plugin or a collection is unknown
+            || ((self.voter_weight_plugin.is_some()
+                || self.voting_mode == VotingMode::NftWeighted)
+                && matches!(self.quorum, Quorum::Percentage(_)))
+        {
+            return Err(VotingErrorCode::InvalidGovernanceConfig.into());
+        }
+
+        Ok(())
+    }
+
+    /// Delegated voting power is counted, which excludes the weights bound
+    /// to the delegate: lockups, NFTs and voter weight records, and the voice
+    /// credits of the quadratic mode, spent vote by vote.
+    pub fn supports_delegation(&self) -> bool {
+        self.voter_weight_plugin.is_none()
+            && !matches!(
+                self.voting_mode,
+                VotingMode::Quadratic | VotingMode::VoteEscrow | VotingMode::NftWeighted
+            )
+    }
+
+    /// Time a proposal that succeeded at `succeeded_at` can be executed from,
+    /// once the timelock elapsed, and the last time it can be executed at when
+    /// the config has a grace period.
+    pub fn execution_window(&self, succeeded_at: i64) -> anchor_lang::Result<(i64, Option<i64>)> {
+        let executable_at = succeeded_at
+            .checked_add(self.execution_delay)
+            .ok_or(VotingErrorCode::Overflow)?;
+        let expires_at = self
+            .execution_grace_period
+            .map(|grace_period| executable_at.saturating_add(grace_period));
+
+        Ok((executable_at, expires_at))
+    }
+
+    /// The option a proposal is decided for by the community, if its votes
+    /// reach the quorum and the leading option meets the approval threshold.
+    ///
+    /// Abstentions count toward the quorum but not toward the threshold.
+    pub fn winning_option(
+        &self,
+        max_vote_weight: u64,
+        tallies: &[u64],
+        votes_abstain: u64,
+    ) -> Option<u8> {
+        winning_option(
+            &self.quorum,
+            &self.approval_threshold,
+            max_vote_weight,
+            tallies,
+            votes_abstain,
+        )
+    }
+
+    /// The option a proposal is decided for by the council, held to the
+    /// council quorum and approval threshold.
+    pub fn council_winning_option(
+        &self,
+        max_vote_weight: u64,
+        tallies: &[u64],
+        votes_abstain: u64,
+    ) -> Option<u8> {
+        winning_option(
+            &self.council_quorum,
+            &self.council_approval_threshold,
+            max_vote_weight,
+            tallies,
+            votes_abstain,
+        )
+    }
+}
+
+fn winning_option(
+    quorum: &Quorum,
+    approval_threshold: &ApprovalThreshold,
+    max_vote_weight: u64,
+    tallies: &[u64],
+    votes_abstain: u64,
+) -> Option<u8> {
+    let total = tallies.iter().sum::<u64>();
+    if !quorum.is_reached(max_vote_weight, total.saturating_add(votes_abstain)) {
+        return None;
+    }
+
+    let (winner, &winner_votes) = tallies
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, votes)| **votes)?;
+    let runner_up_votes = tallies
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != winner)
+        .map(|(_, votes)| *votes)
+        .max()
+        .unwrap_or(0);
+
+    if !approval_threshold.is_met(winner_votes, runner_up_votes, total) {
+        return None;
+    }
+
+    Some(winner as u8)
+}
+
+impl Default for GovernanceConfig {
+    fn default() -> Self {
+        Self {
+            min_voting_period: 1,
+            max_voting_period: 30 * 24 * 60 * 60,
+            reveal_period: 7 * 24 * 60 * 60,
+            quorum: Quorum::Absolute(1),
+            approval_threshold: ApprovalThreshold::SimpleMajority,
+            voting_mode: VotingMode::OneMemberOneVote,
+            community_mint: None,
+            max_lockup_period: 4 * 365 * 24 * 60 * 60,
+            voter_weight_plugin: None,
+            nft_collection: None,
+            membership_root: None,
+            execution_delay: 0,
+            execution_grace_period: None,
+            council: None,
+            council_chamber: None,
+            council_quorum: Quorum::Absolute(1),
+            council_approval_threshold: ApprovalThreshold::SimpleMajority,
+            allow_authority_updates: true,
+        }
+    }
+}
+
+/// Minimum participation for a proposal to be valid.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quorum {
+    /// Number of votes
+    Absolute(u64),
+    /// Share of the maximum vote weight in basis points
+    Percentage(u16),
+}
+
+impl Quorum {
+    pub const LEN: usize = 1 + 8;
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Quorum::Absolute(_) => true,
+            Quorum::Percentage(bps) => *bps > 0 && *bps <= 10_000,
+        }
+    }
+
+    pub fn is_reached(&self, max_vote_weight: u64, votes: u64) -> bool {
+        match self {
+            Quorum::Absolute(min_votes) => votes >= *min_votes,
+            Quorum::Percentage(bps) => {
+                votes as u128 * 10_000 >= *bps as u128 * max_vote_weight as u128
+            }
+        }
+    }
+}
+
+/// How the weight of a revealed vote is computed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VotingMode {
+    /// One vote per member, or its weight in the membership snapshot
+    OneMemberOneVote,
+    /// Community tokens the member deposited in its vault
+    TokenWeighted,
+    /// Square root of the voice credits spent on the vote, out of the
+    /// community tokens the member deposited in its vault
+    Quadratic,
+    /// Community tokens the member locked, decaying linearly toward the unlock
+    VoteEscrow,
+    /// One vote per NFT of the governance collection registered on the vote
+    NftWeighted,
+}
+
+impl VotingMode {
+    pub const LEN: usize = 1;
+
+    pub fn requires_community_mint(&self) -> bool {
+        matches!(
+            self,
+            VotingMode::TokenWeighted | VotingMode::Quadratic | VotingMode::VoteEscrow
+        )
+    }
+}
+
+/// Who sits in the council chamber.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CouncilMembership {
+    /// One vote per wallet of the `Council` account
+    Members,
+    /// Council tokens of this mint escrowed on the vote
+    Mint(Pubkey),
+}
+
+impl CouncilMembership {
+    pub const LEN: usize = 1 + 32;
+}
+
+/// Share of the votes the leading option needs to win.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApprovalThreshold {
+    /// More votes than any other option
+    SimpleMajority,
+    /// At least this share of the votes in basis points, above 50%
+    Supermajority(u16),
+}
+
+impl ApprovalThreshold {
+    pub const LEN: usize = 1 + 2;
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            ApprovalThreshold::SimpleMajority => true,
+            ApprovalThreshold::Supermajority(bps) => *bps > 5_000 && *bps <= 10_000,
+        }
+    }
+
+    pub fn is_met(&self, winner_votes: u64, runner_up_votes: u64, total: u64) -> bool {
+        match self {
+            ApprovalThreshold::SimpleMajority => winner_votes > runner_up_votes,
+            ApprovalThreshold::Supermajority(bps) => {
+                total > 0 && winner_votes as u128 * 10_000 >= *bps as u128 * total as u128
+            }
+        }
+    }
+}
+
+#[account]
+pub struct Proposal {
+    governance: Pubkey,
+    pub creator: Pubkey,
+    title: String,
+    pub options: Vec<String>,
+    pub tallies: Vec<u64>,
+    pub votes_abstain: u64,
+    /// Chamber voting on the proposal
+    pub chamber: Chamber,
+    pub council_tallies: Vec<u64>,
+    pub council_votes_abstain: u64,
+    /// Maximum vote weight of the council when the voting started
+    pub council_max_vote_weight: u64,
+    start: i64,
+    end: i64,
+    pub reveal_end: i64,
+    /// Maximum vote weight of the governance when the voting started, for the quorum
+    pub max_vote_weight: u64,
+    pub state: ProposalState,
+    pub winning_option: Option<u8>,
+    /// Time the proposal was finalized as succeeded, the execution timelock starts then
+    pub succeeded_at: i64,
+    /// Execution window of the governance config at the success of the proposal
+    pub executable_at: i64,
+    pub expires_at: Option<i64>,
+    /// Number of transactions attached to each option
+    pub transaction_counts: Vec<u16>,
+    /// Transactions of the winning option executed so far
+    pub executed_transactions: u16,
+    /// Option carrying a config change not applied yet
+    pub config_change_option: Option<u8>,
+    pub config_change_applied: bool,
+}
+
+impl Proposal {
+    pub fn space(title: &str, options: &[String]) -> usize {
+        let title_len = 4 + title.len();
+        let options_len = 4 + options.iter().map(|option| 4 + option.len()).sum::<usize>();
+        let tallies_len = 4 + 8 * options.len();
+        let transaction_counts_len = 4 + 2 * options.len();
+
+        8 + 32
+            + 32
+            + title_len
+            + options_len
+            + tallies_len
+            + 8
+            + Chamber::LEN
+            + tallies_len
+            + 8
+            + 8
+            + 8
+            + 8
+            + 8
+            + 8
+            + 1
+            + 2
+            + 8
+            + 8
+            + 1
+            + 8
+            + transaction_counts_len
+            + 2
+            + 2
+            + 1
+    }
+
+    /// Checks the proposal can be executed at `now`: the timelock elapsed and
+    /// the grace period, if any, did not.
+    pub fn check_execution(&self, now: i64) -> anchor_lang::Result<()> {
+        if now < self.executable_at {
+            return Err(VotingErrorCode::TimelockNotElapsed.into());
+        }
+
+        if self.is_expired(now) {
+            return Err(VotingErrorCode::ProposalExpired.into());
+        }
+
+        Ok(())
+    }
+
+    /// The grace period of the proposal ended at `now`.
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now > expires_at)
+    }
+
+    /// The winning option has transactions or a config change not executed yet.
+    pub fn has_pending_execution(&self) -> bool {
+        self.winning_option.is_some_and(|option| {
+            self.executed_transactions < self.transaction_counts[option as usize]
+                || self.config_change_option == Some(option)
+        })
+    }
+
+    /// Phase of the proposal at `now`. The stored state only moves when an
+    /// instruction runs: a proposal is revealing once its voting period ended,
+    /// before any reveal, and a succeeded proposal is executed once its
+    /// timelock elapsed if its winning option has nothing to execute, or
+    /// expired after its grace period otherwise.
+    pub fn current_state(&self, now: i64) -> ProposalState {
+        match self.state {
+            ProposalState::Voting if now >= self.end => ProposalState::Revealing,
+            ProposalState::Succeeded
+                if !self.has_pending_execution() && now >= self.executable_at =>
+            {
+                ProposalState::Executed
+            }
+            ProposalState::Succeeded if self.is_expired(now) => ProposalState::Expired,
+            state => state,
+        }
+    }
+}
+
+/// Instructions executed together if an option of a proposal wins.
+#[account]
+pub struct ProposalTransaction {
+    pub proposal: Pubkey,
+    pub option: u8,
+    pub index: u16,
+    pub instructions: Vec<ProposalInstruction>,
+    pub executed: bool,
+}
+
+impl ProposalTransaction {
+    pub fn space(instructions: &[ProposalInstruction]) -> usize {
+        let instructions_len = 4 + instructions
+            .iter()
+            .map(ProposalInstruction::space)
+            .sum::<usize>();
+
+        8 + 32 + 1 + 2 + instructions_len + 1
+    }
+}
+
+/// Governance config a proposal applies if an option wins.
+#[account]
+pub struct ConfigChange {
+    pub proposal: Pubkey,
+    pub option: u8,
+    pub config: GovernanceConfig,
+}
+
+/// Serialized instruction of a proposal transaction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProposalInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<ProposalAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl ProposalInstruction {
+    pub fn space(&self) -> usize {
+        32 + 4 + self.accounts.len() * ProposalAccountMeta::LEN + 4 + self.data.len()
+    }
+}
+
+impl From<&ProposalInstruction> for Instruction {
+    fn from(instruction: &ProposalInstruction) -> Self {
+        Instruction {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|meta| AccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        }
+    }
+}
+
+impl From<Instruction> for ProposalInstruction {
+    fn from(instruction: Instruction) -> Self {
+        Self {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .into_iter()
+                .map(|meta| ProposalAccountMeta {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: instruction.data,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProposalAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl ProposalAccountMeta {
+    pub const LEN: usize = 32 + 1 + 1;
+}
+
+#[event]
+pub struct ProposalCancelled {
+    pub governance: Pubkey,
+    pub proposal: Pubkey,
+    pub creator: Pubkey,
+}
+
+#[event]
+pub struct ProposalVetoed {
+    pub governance: Pubkey,
+    pub proposal: Pubkey,
+    pub council: Pubkey,
+}
+
+/// What a member votes for.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VoteChoice {
+    /// Index into the proposal options
+    Option(u8),
+    /// Counted toward the quorum only
+    Abstain,
+}
+
+/// Lifecycle of a proposal.
+///
+/// `Draft` -> `Voting` -> `Revealing` -> `Succeeded` | `Defeated`, with
+/// `Succeeded` -> `Executed` | `Expired`. A proposal can be `Cancelled` before
+/// it executes. `Proposal::current_state` gives the phase at a given time,
+/// ahead of the instruction storing it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProposalState {
+    Draft,
+    Voting,
+    Revealing,
+    Succeeded,
+    Defeated,
+    Cancelled,
+    Executed,
+    /// Succeeded, but not executed within the grace period
+    Expired,
+}
+
+impl ProposalState {
+    /// No vote can change the outcome anymore.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            ProposalState::Succeeded
+                | ProposalState::Defeated
+                | ProposalState::Cancelled
+                | ProposalState::Executed
+                | ProposalState::Expired
+        )
+    }
+}
+
+/// Chamber voting on a proposal.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chamber {
+    Community,
+    Council,
+    /// Both chambers, they must pass the same option
+    Both,
+}
+
+impl Chamber {
+    pub const LEN: usize = 1;
+
+    pub fn includes_community(&self) -> bool {
+        matches!(self, Chamber::Community | Chamber::Both)
+    }
+
+    pub fn includes_council(&self) -> bool {
+        matches!(self, Chamber::Council | Chamber::Both)
+    }
+
+    /// The option a proposal is decided for out of the options the community
+    /// and the council passed.
+    pub fn decide(&self, community: Option<u8>, council: Option<u8>) -> Option<u8> {
+        match self {
+            Chamber::Community => community,
+            Chamber::Council => council,
+            Chamber::Both => community.filter(|_| community == council),
+        }
+    }
+}
+
+#[account]
+pub struct VoteCommitment {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    commitment: [u8; 32],
+    pub revealed: bool,
+    /// NFTs registered on the vote in the NFT voting mode
+    pub nft_votes: u64,
+    /// Voice credits spent on the vote in the quadratic mode, given back on close
+    pub credits: u64,
+}
+
+/// Proof that the signer of `join` is in the membership snapshot.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MembershipProof {
+    pub weight: u64,
+    /// Siblings of the member leaf, from the bottom of the tree
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// An NFT backing a membership, so that it cannot back another one.
+#[account]
+pub struct MemberNftRecord {
+    pub governance: Pubkey,
+    pub mint: Pubkey,
+    pub member: Pubkey,
+}
+
+/// An NFT counted on a proposal.
+#[account]
+pub struct NftVoteRecord {
+    pub proposal: Pubkey,
+    pub mint: Pubkey,
+    pub voter: Pubkey,
+}
+
+#[account]
+pub struct User {
+    pub governance: Pubkey,
+    pub authority: Pubkey,
+    points: u64,
+    pub deposited_amount: u64,
+    /// Vote commitments not closed yet, the deposit is locked while non-zero
+    pub active_votes: u64,
+    /// `User` account voting on behalf of this member
+    pub delegate: Option<Pubkey>,
+    pub delegator_count: u64,
+    /// Weight of the member in the membership snapshot, 1 without one
+    pub membership_weight: u64,
+    /// Voice credits spent on the votes not closed yet in the quadratic mode,
+    /// out of the deposit
+    pub credits_spent: u64,
+}
+
+impl User {
+    /// Offset of `delegate` in the account data, to look up the delegators of
+    /// a member with a memcmp filter.
+    pub const DELEGATE_OFFSET: usize = 8 + 32 + 32 + 8 + 8 + 8;
+
+    pub fn vote_weight(
+        &self,
+        config: &GovernanceConfig,
+        credits: u64,
+        lockup: Option<&Lockup>,
+        now: i64,
+    ) -> anchor_lang::Result<u64> {
+        match config.voting_mode {
+            VotingMode::OneMemberOneVote => Ok(self.membership_weight),
+            VotingMode::TokenWeighted => Ok(self.deposited_amount),
+            VotingMode::Quadratic => {
+                if credits > self.deposited_amount.saturating_sub(self.credits_spent) {
+                    return Err(VotingErrorCode::InsufficientDeposit.into());
+                }
+
+                Ok(integer_sqrt(credits as u128))
+            }
+            VotingMode::VoteEscrow => Ok(lockup.map_or(0, |lockup| {
+                lockup.vote_weight(now, config.max_lockup_period)
+            })),
+            // NFTs are registered on the vote commitment, they are not delegated
+            VotingMode::NftWeighted => Ok(0),
+        }
+    }
+}
+
+/// Vote weight of a voter, produced by the governance voter weight plugin.
+///
+/// Mirrors the layout of the plugin account, which the plugin program owns.
+#[account]
+pub struct VoterWeightRecord {
+    pub governance: Pubkey,
+    pub voter: Pubkey,
+    pub voter_weight: u64,
+    /// Last slot the weight can be used in
+    pub voter_weight_expiry: Option<u64>,
+}
+
+/// Community tokens a member locked out of its deposit.
+#[account]
+pub struct Lockup {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
+}
+
+impl Lockup {
+    /// `amount * remaining / max_lockup_period`, so a lockup of the maximum
+    /// duration starts with the full amount and reaches zero at the unlock.
+    pub fn vote_weight(&self, now: i64, max_lockup_period: i64) -> u64 {
+        if max_lockup_period <= 0 {
+            return 0;
+        }
+        let remaining = self
+            .unlock_at
+            .saturating_sub(now)
+            .clamp(0, max_lockup_period);
+
+        (self.amount as u128 * remaining as u128 / max_lockup_period as u128) as u64
+    }
+}
+
+/// Checks `owner` holds the NFT of `token_account`, verified in `collection`.
+///
+/// The metadata account is owned by the token metadata program, which only
+/// creates it at the address derived from its mint.
+pub fn verify_nft(
+    collection: &Pubkey,
+    owner: &Pubkey,
+    token_account: &TokenAccount,
+    metadata: &MetadataAccount,
+) -> anchor_lang::Result<()> {
+    let in_collection = metadata
+        .collection
+        .as_ref()
+        .is_some_and(|metadata_collection| {
+            metadata_collection.verified && metadata_collection.key == *collection
+        });
+    if token_account.owner != *owner
+        || token_account.amount != 1
+        || token_account.mint != metadata.mint
+        || !in_collection
+    {
+        return Err(VotingErrorCode::InvalidNft.into());
+    }
+
+    Ok(())
+}
+
+/// Largest integer whose square is not above `n`.
+fn integer_sqrt(n: u128) -> u64 {
+    if n < 2 {
+        return n as u64;
+    }
+
+    // Newton's method from a power of two above the root, which keeps
+    // `x + n / x` far from overflowing
+    let bits = u128::BITS - n.leading_zeros();
+    let mut x = 1u128 << bits.div_ceil(2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x as u64;
+        }
+        x = y;
+    }
+}
+
+#[error_code]
+pub enum VotingErrorCode {
+    #[msg("Voting has not started yet")]
+    NotStarted,
+
+    #[msg("Voting ended")]
+    VotingEnded,
+
+    #[msg("Voting not ended")]
+    VotingNotEnded,
+
+    #[msg("Invalid Commitment")]
+    InvalidCommitment,
+
+    #[msg("Signer is not allowed to perform this action")]
+    Unauthorized,
+
+    #[msg("Invalid governance config")]
+    InvalidGovernanceConfig,
+
+    #[msg("Voting already started")]
+    VotingAlreadyStarted,
+
+    #[msg("Voting end must be in the future")]
+    VotingEndInPast,
+
+    #[msg("Voting period is shorter than the governance minimum")]
+    VotingPeriodTooShort,
+
+    #[msg("Voting period is longer than the governance maximum")]
+    VotingPeriodTooLong,
+
+    #[msg("Signer is not a member of the governance")]
+    NotAMember,
+
+    #[msg("Vote already revealed")]
+    AlreadyRevealed,
+
+    #[msg("Proposal is not finalized yet")]
+    ProposalNotFinalized,
+
+    #[msg("Instruction is not allowed in the current proposal state")]
+    InvalidProposalState,
+
+    #[msg("Reveal period ended")]
+    RevealEnded,
+
+    #[msg("Reveal period not ended")]
+    RevealNotEnded,
+
+    #[msg("Arithmetic overflow")]
+    Overflow,
+
+    #[msg("Invalid proposal options")]
+    InvalidOptions,
+
+    #[msg("Mint is not the governance community mint")]
+    InvalidMint,
+
+    #[msg("Voter has no voting power")]
+    NoVotingPower,
+
+    #[msg("Deposit is locked by an active vote")]
+    TokensLocked,
+
+    #[msg("Amount exceeds the deposit")]
+    InsufficientDeposit,
+
+    #[msg("Invalid lockup duration")]
+    InvalidLockupDuration,
+
+    #[msg("Lockup has not expired yet")]
+    LockupNotExpired,
+
+    #[msg("Invalid delegate")]
+    InvalidDelegate,
+
+    #[msg("Voting power is delegated")]
+    VoteDelegated,
+
+    #[msg("Delegation is locked by an active vote")]
+    DelegationLocked,
+
+    #[msg("Invalid voter weight record")]
+    InvalidVoterWeightRecord,
+
+    #[msg("Voter weight record expired")]
+    VoterWeightRecordExpired,
+
+    #[msg("Not an NFT of the governance collection held by the signer")]
+    InvalidNft,
+
+    #[msg("Signer is not in the membership snapshot")]
+    NotEligible,
+
+    #[msg("Invalid proposal transaction")]
+    InvalidTransaction,
+
+    #[msg("Proposal transaction already executed")]
+    TransactionAlreadyExecuted,
+
+    #[msg("Execution timelock has not elapsed")]
+    TimelockNotElapsed,
+
+    #[msg("Proposal expired unexecuted")]
+    ProposalExpired,
+
+    #[msg("Chamber not set up in the governance")]
+    InvalidChamber,
+
+    #[msg("Invalid council members")]
+    InvalidCouncil,
+
+    #[msg("Signer is not a council member")]
+    NotACouncilMember,
+
+    #[msg("Governance config can only change through proposals")]
+    AuthorityUpdatesDisabled,
+
+    #[msg("Delegation is not supported by the voting mode or the voter weight plugin")]
+    DelegationNotSupported,
 }
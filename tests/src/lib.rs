@@ -1,15 +1,30 @@
+#![allow(clippy::result_large_err)]
+
 use std::{str::FromStr, sync::Arc};
 
+use allowlist_plugin::AllowlistEntry;
 use anchor_client::{
+    solana_client::rpc_filter::{Memcmp, RpcFilterType},
     solana_sdk::{
         commitment_config::CommitmentConfig,
+        instruction::{AccountMeta, Instruction},
         signature::{read_keypair_file, Keypair, Signature},
         signer::Signer,
     },
     Client, ClientError, Cluster, Program,
 };
 use anchor_lang::system_program;
-use solana_program::pubkey::Pubkey;
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    metadata::mpl_token_metadata::{
+        accounts::{MasterEdition, Metadata},
+        instructions::{CreateV1Builder, MintV1Builder, VerifyCollectionV1Builder},
+        types::{Collection, CollectionDetails, TokenStandard},
+    },
+    token::spl_token,
+};
+use solana_program::{program_pack::Pack, pubkey::Pubkey, system_instruction};
+use voting::{Chamber, GovernanceConfig, MembershipProof, ProposalTransaction, User, VoteChoice};
 
 #[allow(unused_imports)]
 mod test;
@@ -23,11 +38,22 @@ pub struct TestSetup {
     pub program: Program<Arc<Keypair>>,
 }
 
+impl Default for TestSetup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl TestSetup {
     pub fn new() -> Self {
-        let program_id = PROGRAM_ID;
         let anchor_wallet = std::env::var("ANCHOR_WALLET").unwrap();
         let payer = read_keypair_file(&anchor_wallet).unwrap();
+
+        Self::new_with_payer(payer)
+    }
+
+    pub fn new_with_payer(payer: Keypair) -> Self {
+        let program_id = PROGRAM_ID;
         let payer = Arc::new(payer);
 
         let client = Client::new_with_options(
@@ -46,6 +72,18 @@ impl TestSetup {
         }
     }
 
+    /// Transfers lamports from the payer, e.g. to fund a second test wallet
+    pub fn fund(&self, to: &Pubkey, lamports: u64) -> Result<Signature, ClientError> {
+        self.program
+            .request()
+            .instruction(system_instruction::transfer(
+                &self.payer.pubkey(),
+                to,
+                lamports,
+            ))
+            .send()
+    }
+
     pub fn get_governance_pda(&self, name: &str) -> Pubkey {
         let (governance_pda, _bump) =
             Pubkey::find_program_address(&[b"governance", name.as_bytes()], &self.program_id);
@@ -94,7 +132,351 @@ impl TestSetup {
         vote_pda
     }
 
+    pub fn get_treasury_pda(&self, name: &str) -> Pubkey {
+        let governance_pda = self.get_governance_pda(name);
+        let (signer_pda, _bump) =
+            Pubkey::find_program_address(&[b"treasury", governance_pda.as_ref()], &self.program_id);
+
+        signer_pda
+    }
+
+    pub fn get_proposal_transaction_pda(
+        &self,
+        name: &str,
+        title: &str,
+        option: u8,
+        index: u16,
+    ) -> Pubkey {
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let (transaction_pda, _bump) = Pubkey::find_program_address(
+            &[
+                b"proposal_transaction",
+                proposal_pda.as_ref(),
+                &[option],
+                &index.to_le_bytes(),
+            ],
+            &self.program_id,
+        );
+
+        transaction_pda
+    }
+
+    pub fn get_council_pda(&self, name: &str) -> Pubkey {
+        let governance_pda = self.get_governance_pda(name);
+        Pubkey::find_program_address(
+            &[voting::constants::COUNCIL_SEED, governance_pda.as_ref()],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn get_council_vote_pda(&self, name: &str, title: &str) -> Pubkey {
+        let proposal_pda = self.get_proposal_pda(name, title);
+        Pubkey::find_program_address(
+            &[
+                voting::constants::COUNCIL_VOTE_SEED,
+                proposal_pda.as_ref(),
+                self.payer.pubkey().as_ref(),
+            ],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn get_council_escrow_pda(&self, name: &str, title: &str) -> Pubkey {
+        let proposal_pda = self.get_proposal_pda(name, title);
+        Pubkey::find_program_address(
+            &[
+                voting::constants::COUNCIL_ESCROW_SEED,
+                proposal_pda.as_ref(),
+                self.payer.pubkey().as_ref(),
+            ],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn get_config_change_pda(&self, name: &str, title: &str) -> Pubkey {
+        let proposal_pda = self.get_proposal_pda(name, title);
+        Pubkey::find_program_address(
+            &[voting::constants::CONFIG_CHANGE_SEED, proposal_pda.as_ref()],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn get_member_nft_record_pda(&self, name: &str, mint: &Pubkey) -> Pubkey {
+        let governance_pda = self.get_governance_pda(name);
+        Pubkey::find_program_address(
+            &[
+                voting::constants::MEMBER_NFT_SEED,
+                governance_pda.as_ref(),
+                mint.as_ref(),
+            ],
+            &self.program_id,
+        )
+        .0
+    }
+
+    pub fn get_vault_pda(&self, name: &str) -> Pubkey {
+        let governance_pda = self.get_governance_pda(name);
+        let (vault_pda, _bump) = Pubkey::find_program_address(
+            &[
+                b"vault",
+                governance_pda.as_ref(),
+                self.payer.pubkey().as_ref(),
+            ],
+            &self.program_id,
+        );
+
+        vault_pda
+    }
+
+    pub fn get_lockup_pda(&self, name: &str) -> Pubkey {
+        let user_pda = self.get_user_pda(name);
+        let (lockup_pda, _bump) =
+            Pubkey::find_program_address(&[b"lockup", user_pda.as_ref()], &self.program_id);
+
+        lockup_pda
+    }
+
+    pub fn get_registrar_pda(&self, name: &str) -> Pubkey {
+        let governance_pda = self.get_governance_pda(name);
+        let (registrar_pda, _bump) = Pubkey::find_program_address(
+            &[b"registrar", governance_pda.as_ref()],
+            &allowlist_plugin::ID,
+        );
+
+        registrar_pda
+    }
+
+    pub fn get_voter_weight_record_pda(&self, name: &str) -> Pubkey {
+        let governance_pda = self.get_governance_pda(name);
+        let (voter_weight_record_pda, _bump) = Pubkey::find_program_address(
+            &[
+                b"voter_weight_record",
+                governance_pda.as_ref(),
+                self.payer.pubkey().as_ref(),
+            ],
+            &allowlist_plugin::ID,
+        );
+
+        voter_weight_record_pda
+    }
+
+    /// Creates the allowlist plugin registrar of the governance
+    pub fn create_registrar(
+        &self,
+        name: &str,
+        entries: Vec<AllowlistEntry>,
+    ) -> Result<Signature, ClientError> {
+        self.client
+            .program(allowlist_plugin::ID)?
+            .request()
+            .accounts(allowlist_plugin::accounts::CreateRegistrar {
+                governance: self.get_governance_pda(name),
+                registrar: self.get_registrar_pda(name),
+                authority: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(allowlist_plugin::instruction::CreateRegistrar { entries })
+            .send()
+    }
+
+    /// Creates a mint without decimals with the payer as mint authority
+    pub fn create_mint(&self, mint: &Keypair) -> Result<Signature, ClientError> {
+        let rent = self
+            .program
+            .rpc()
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
+
+        self.program
+            .request()
+            .instruction(system_instruction::create_account(
+                &self.payer.pubkey(),
+                &mint.pubkey(),
+                rent,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ))
+            .instruction(
+                spl_token::instruction::initialize_mint2(
+                    &spl_token::ID,
+                    &mint.pubkey(),
+                    &self.payer.pubkey(),
+                    None,
+                    0,
+                )
+                .unwrap(),
+            )
+            .signer(mint)
+            .send()
+    }
+
+    /// Creates a token account owned by the payer and mints `amount` to it
+    pub fn create_token_account(
+        &self,
+        account: &Keypair,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let rent = self
+            .program
+            .rpc()
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)?;
+
+        self.program
+            .request()
+            .instruction(system_instruction::create_account(
+                &self.payer.pubkey(),
+                &account.pubkey(),
+                rent,
+                spl_token::state::Account::LEN as u64,
+                &spl_token::ID,
+            ))
+            .instruction(
+                spl_token::instruction::initialize_account3(
+                    &spl_token::ID,
+                    &account.pubkey(),
+                    mint,
+                    &self.payer.pubkey(),
+                )
+                .unwrap(),
+            )
+            .instruction(
+                spl_token::instruction::mint_to(
+                    &spl_token::ID,
+                    mint,
+                    &account.pubkey(),
+                    &self.payer.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            )
+            .signer(account)
+            .send()
+    }
+
+    /// Creates an NFT and mints it to the payer. Without `collection` the NFT
+    /// is created as a collection itself
+    pub fn create_nft(
+        &self,
+        mint: &Keypair,
+        collection: Option<&Pubkey>,
+    ) -> Result<Signature, ClientError> {
+        let metadata = Metadata::find_pda(&mint.pubkey()).0;
+        let master_edition = MasterEdition::find_pda(&mint.pubkey()).0;
+
+        let mut create = CreateV1Builder::new();
+        create
+            .metadata(metadata)
+            .master_edition(Some(master_edition))
+            .mint(mint.pubkey(), true)
+            .authority(self.payer.pubkey())
+            .payer(self.payer.pubkey())
+            .update_authority(self.payer.pubkey(), true)
+            .name("Superteam".to_string())
+            .uri(String::new())
+            .seller_fee_basis_points(0)
+            .token_standard(TokenStandard::NonFungible);
+        match collection {
+            Some(collection) => create.collection(Collection {
+                verified: false,
+                key: *collection,
+            }),
+            None => create.collection_details(CollectionDetails::V1 { size: 0 }),
+        };
+
+        let mint_ix = MintV1Builder::new()
+            .token(get_associated_token_address(
+                &self.payer.pubkey(),
+                &mint.pubkey(),
+            ))
+            .token_owner(Some(self.payer.pubkey()))
+            .metadata(metadata)
+            .master_edition(Some(master_edition))
+            .mint(mint.pubkey())
+            .authority(self.payer.pubkey())
+            .payer(self.payer.pubkey())
+            .amount(1)
+            .instruction();
+
+        self.program
+            .request()
+            .instruction(create.instruction())
+            .instruction(mint_ix)
+            .signer(mint)
+            .send()
+    }
+
+    /// Verifies the NFT `mint` as a member of `collection`
+    pub fn verify_collection(
+        &self,
+        mint: &Pubkey,
+        collection: &Pubkey,
+    ) -> Result<Signature, ClientError> {
+        let verify_ix = VerifyCollectionV1Builder::new()
+            .authority(self.payer.pubkey())
+            .metadata(Metadata::find_pda(mint).0)
+            .collection_mint(*collection)
+            .collection_metadata(Some(Metadata::find_pda(collection).0))
+            .collection_master_edition(Some(MasterEdition::find_pda(collection).0))
+            .instruction();
+
+        self.program.request().instruction(verify_ix).send()
+    }
+
+    /// Creates the associated token account of `owner`, e.g. of the treasury
+    pub fn create_associated_token_account(
+        &self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+    ) -> Result<Signature, ClientError> {
+        self.program
+            .request()
+            .instruction(
+                spl_associated_token_account::instruction::create_associated_token_account(
+                    &self.payer.pubkey(),
+                    owner,
+                    mint,
+                    &spl_token::ID,
+                ),
+            )
+            .send()
+    }
+
+    pub fn mint_to(
+        &self,
+        mint: &Pubkey,
+        account: &Pubkey,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        self.program
+            .request()
+            .instruction(
+                spl_token::instruction::mint_to(
+                    &spl_token::ID,
+                    mint,
+                    account,
+                    &self.payer.pubkey(),
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            )
+            .send()
+    }
+
     pub fn create_governance(&self, name: &str) -> Result<Signature, ClientError> {
+        self.create_governance_with_config(name, GovernanceConfig::default())
+    }
+
+    pub fn create_governance_with_config(
+        &self,
+        name: &str,
+        config: GovernanceConfig,
+    ) -> Result<Signature, ClientError> {
         let governance_pda = self.get_governance_pda(name);
 
         self.program
@@ -106,7 +488,25 @@ impl TestSetup {
             })
             .args(voting::instruction::CreateGovernance {
                 name: name.to_string(),
+                config,
+            })
+            .send()
+    }
+
+    pub fn update_governance_config(
+        &self,
+        name: &str,
+        config: GovernanceConfig,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::UpdateGovernanceConfig {
+                governance: governance_pda,
+                authority: self.payer.pubkey(),
             })
+            .args(voting::instruction::UpdateGovernanceConfig { config })
             .send()
     }
 
@@ -117,99 +517,795 @@ impl TestSetup {
         self.program
             .request()
             .accounts(voting::accounts::Join {
+                governance: governance_pda,
+                user: user_pda,
+                nft_token_account: None,
+                nft_metadata: None,
+                member_nft_record: None,
+                authority: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::Join { membership: None })
+            .send()
+    }
+
+    /// Joins a governance with a membership snapshot, proving the payer has
+    /// `weight` in it
+    pub fn join_with_proof(
+        &self,
+        name: &str,
+        weight: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let user_pda = self.get_user_pda(name);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::Join {
+                governance: governance_pda,
                 user: user_pda,
+                nft_token_account: None,
+                nft_metadata: None,
+                member_nft_record: None,
                 authority: self.payer.pubkey(),
                 system_program: system_program::ID,
             })
             .args(voting::instruction::Join {
-                governance_key: governance_pda,
+                membership: Some(MembershipProof { weight, proof }),
             })
             .send()
     }
 
-    pub fn create_proposal(&self, name: &str, title: &str) -> Result<Signature, ClientError> {
+    /// Joins a governance gated on an NFT collection, holding the NFT `mint`
+    pub fn join_with_nft(&self, name: &str, mint: &Pubkey) -> Result<Signature, ClientError> {
         let governance_pda = self.get_governance_pda(name);
-        let proposal_pda = self.get_proposal_pda(name, title);
+        let user_pda = self.get_user_pda(name);
 
         self.program
             .request()
-            .accounts(voting::accounts::CreateProposal {
-                proposal: proposal_pda,
-                user: self.payer.pubkey(),
+            .accounts(voting::accounts::Join {
+                governance: governance_pda,
+                user: user_pda,
+                nft_token_account: Some(get_associated_token_address(&self.payer.pubkey(), mint)),
+                nft_metadata: Some(Metadata::find_pda(mint).0),
+                member_nft_record: Some(self.get_member_nft_record_pda(name, mint)),
+                authority: self.payer.pubkey(),
                 system_program: system_program::ID,
             })
-            .args(voting::instruction::CreateProposal {
-                governance_key: governance_pda,
-                title: title.as_bytes().to_vec(),
+            .args(voting::instruction::Join { membership: None })
+            .send()
+    }
+
+    /// Delegates the voting power of the payer to the member `delegate`
+    pub fn delegate(&self, name: &str, delegate: &Pubkey) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let (delegate_pda, _bump) = Pubkey::find_program_address(
+            &[b"user", governance_pda.as_ref(), delegate.as_ref()],
+            &self.program_id,
+        );
+
+        self.program
+            .request()
+            .accounts(voting::accounts::Delegate {
+                governance: governance_pda,
+                user: self.get_user_pda(name),
+                delegate: delegate_pda,
+                authority: self.payer.pubkey(),
             })
+            .args(voting::instruction::Delegate {})
             .send()
     }
 
-    pub fn start_vote(&self, name: &str, title: &str, end: i64) -> Result<Signature, ClientError> {
-        let proposal_pda = self.get_proposal_pda(name, title);
+    pub fn undelegate(&self, name: &str) -> Result<Signature, ClientError> {
+        let user_pda = self.get_user_pda(name);
+        let user: User = self.program.account(user_pda)?;
 
         self.program
             .request()
-            .accounts(voting::accounts::StartVote {
-                proposal: proposal_pda,
+            .accounts(voting::accounts::Undelegate {
+                governance: self.get_governance_pda(name),
+                user: user_pda,
+                delegate: user.delegate.unwrap_or_default(),
+                authority: self.payer.pubkey(),
             })
-            .args(voting::instruction::StartVote { end })
+            .args(voting::instruction::Undelegate {})
             .send()
     }
 
-    pub fn commit_vote(
+    /// `User` accounts delegating to the payer
+    pub fn get_delegators(&self, name: &str) -> Result<Vec<Pubkey>, ClientError> {
+        let user_pda = self.get_user_pda(name);
+        let mut delegate = vec![1];
+        delegate.extend_from_slice(user_pda.as_ref());
+
+        let delegators =
+            self.program
+                .accounts::<User>(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                    User::DELEGATE_OFFSET,
+                    delegate,
+                ))])?;
+
+        Ok(delegators.into_iter().map(|(pubkey, _)| pubkey).collect())
+    }
+
+    pub fn deposit_governing_tokens(
         &self,
         name: &str,
-        title: &str,
-        vote: u8,
-        salt: &str,
+        mint: &Pubkey,
+        source: &Pubkey,
+        amount: u64,
     ) -> Result<Signature, ClientError> {
         let governance_pda = self.get_governance_pda(name);
-        let proposal_pda = self.get_proposal_pda(name, title);
-        let vote_pda = self.get_vote_pda(name, title);
-
-        let vote = vote == 1;
-        let temp = format!("{}{}", vote, salt);
-        let commitment = solana_program::hash::hash(temp.as_bytes());
+        let user_pda = self.get_user_pda(name);
+        let vault_pda = self.get_vault_pda(name);
 
         self.program
             .request()
-            .accounts(voting::accounts::CommitVote {
+            .accounts(voting::accounts::DepositGoverningTokens {
                 governance: governance_pda,
-                proposal: proposal_pda,
-                vote_commitment: vote_pda,
-                user: self.payer.pubkey(),
+                user: user_pda,
+                community_mint: *mint,
+                vault: vault_pda,
+                source: *source,
+                authority: self.payer.pubkey(),
+                token_program: spl_token::ID,
                 system_program: system_program::ID,
             })
-            .args(voting::instruction::CommitVote {
-                commitment: commitment.to_string(),
-            })
+            .args(voting::instruction::DepositGoverningTokens { amount })
             .send()
     }
 
-    pub fn reveal_vote(
+    pub fn withdraw_governing_tokens(
         &self,
         name: &str,
-        title: &str,
-        vote: u8,
-        salt: &str,
+        destination: &Pubkey,
+        amount: u64,
     ) -> Result<Signature, ClientError> {
-        let proposal_pda = self.get_proposal_pda(name, title);
-        let vote_pda = self.get_vote_pda(name, title);
+        let governance_pda = self.get_governance_pda(name);
         let user_pda = self.get_user_pda(name);
-        let vote = vote == 1;
+        let vault_pda = self.get_vault_pda(name);
 
         self.program
             .request()
-            .accounts(voting::accounts::RevealVote {
-                proposal: proposal_pda,
-                vote_commitment: vote_pda,
+            .accounts(voting::accounts::WithdrawGoverningTokens {
+                governance: governance_pda,
                 user: user_pda,
+                vault: vault_pda,
+                destination: *destination,
+                authority: self.payer.pubkey(),
+                token_program: spl_token::ID,
             })
-            .args(voting::instruction::RevealVote {
-                vote,
-                salt: salt.to_string(),
+            .args(voting::instruction::WithdrawGoverningTokens { amount })
+            .send()
+    }
+
+    pub fn create_lockup(
+        &self,
+        name: &str,
+        amount: u64,
+        duration: i64,
+    ) -> Result<Signature, ClientError> {
+        self.program
+            .request()
+            .accounts(voting::accounts::CreateLockup {
+                governance: self.get_governance_pda(name),
+                user: self.get_user_pda(name),
+                lockup: self.get_lockup_pda(name),
+                authority: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::CreateLockup { amount, duration })
+            .send()
+    }
+
+    pub fn extend_lockup(&self, name: &str, duration: i64) -> Result<Signature, ClientError> {
+        self.program
+            .request()
+            .accounts(voting::accounts::ExtendLockup {
+                governance: self.get_governance_pda(name),
+                user: self.get_user_pda(name),
+                lockup: self.get_lockup_pda(name),
+                authority: self.payer.pubkey(),
+            })
+            .args(voting::instruction::ExtendLockup { duration })
+            .send()
+    }
+
+    pub fn unlock(&self, name: &str) -> Result<Signature, ClientError> {
+        self.program
+            .request()
+            .accounts(voting::accounts::Unlock {
+                governance: self.get_governance_pda(name),
+                user: self.get_user_pda(name),
+                lockup: self.get_lockup_pda(name),
+                authority: self.payer.pubkey(),
+            })
+            .args(voting::instruction::Unlock {})
+            .send()
+    }
+
+    pub fn create_proposal(&self, name: &str, title: &str) -> Result<Signature, ClientError> {
+        self.create_proposal_with_options(name, title, &["Yes", "No"])
+    }
+
+    pub fn create_proposal_with_options(
+        &self,
+        name: &str,
+        title: &str,
+        options: &[&str],
+    ) -> Result<Signature, ClientError> {
+        self.create_proposal_in_chamber(name, title, options, Chamber::Community)
+    }
+
+    pub fn create_proposal_in_chamber(
+        &self,
+        name: &str,
+        title: &str,
+        options: &[&str],
+        chamber: Chamber,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let user_pda = self.get_user_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::CreateProposal {
+                governance: governance_pda,
+                member: user_pda,
+                proposal: proposal_pda,
+                user: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::CreateProposal {
+                title: title.as_bytes().to_vec(),
+                options: options.iter().map(|option| option.to_string()).collect(),
+                chamber,
+            })
+            .send()
+    }
+
+    pub fn start_vote(&self, name: &str, title: &str, end: i64) -> Result<Signature, ClientError> {
+        self.start_vote_with_council_mint(name, title, end, None)
+    }
+
+    pub fn start_vote_with_council_mint(
+        &self,
+        name: &str,
+        title: &str,
+        end: i64,
+        council_mint: Option<Pubkey>,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::StartVote {
+                governance: governance_pda,
+                proposal: proposal_pda,
+                council_mint,
+                authority: self.payer.pubkey(),
+            })
+            .args(voting::instruction::StartVote { end })
+            .send()
+    }
+
+    pub fn commit_vote(
+        &self,
+        name: &str,
+        title: &str,
+        choice: VoteChoice,
+        salt: &str,
+    ) -> Result<Signature, ClientError> {
+        self.commit_vote_with_credits(name, title, choice, 0, salt)
+    }
+
+    pub fn commit_vote_with_credits(
+        &self,
+        name: &str,
+        title: &str,
+        choice: VoteChoice,
+        credits: u64,
+        salt: &str,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let user_pda = self.get_user_pda(name);
+        let vote_pda = self.get_vote_pda(name, title);
+
+        let salt = voting::commitment::salt_from_secret(salt);
+        let commitment = voting::commitment::hash_vote(
+            &proposal_pda,
+            &self.payer.pubkey(),
+            choice,
+            credits,
+            &salt,
+        );
+
+        self.program
+            .request()
+            .accounts(voting::accounts::CommitVote {
+                governance: governance_pda,
+                proposal: proposal_pda,
+                member: user_pda,
+                vote_commitment: vote_pda,
+                user: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::CommitVote { commitment })
+            .send()
+    }
+
+    pub fn reveal_vote(
+        &self,
+        name: &str,
+        title: &str,
+        choice: VoteChoice,
+        salt: &str,
+    ) -> Result<Signature, ClientError> {
+        self.reveal_vote_with_credits(name, title, choice, 0, salt)
+    }
+
+    pub fn reveal_vote_with_credits(
+        &self,
+        name: &str,
+        title: &str,
+        choice: VoteChoice,
+        credits: u64,
+        salt: &str,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let vote_pda = self.get_vote_pda(name, title);
+        let user_pda = self.get_user_pda(name);
+        let lockup_pda = self.get_lockup_pda(name);
+        let lockup = self
+            .program
+            .account::<voting::Lockup>(lockup_pda)
+            .ok()
+            .map(|_| lockup_pda);
+        let delegators = self
+            .get_delegators(name)?
+            .into_iter()
+            .map(|delegator| AccountMeta::new_readonly(delegator, false))
+            .collect::<Vec<_>>();
+
+        self.program
+            .request()
+            .accounts(voting::accounts::RevealVote {
+                governance: governance_pda,
+                proposal: proposal_pda,
+                vote_commitment: vote_pda,
+                user: user_pda,
+                lockup,
+                voter_weight_record: None,
+                voter: self.payer.pubkey(),
+            })
+            .accounts(delegators)
+            .args(voting::instruction::RevealVote {
+                choice,
+                credits,
+                salt: voting::commitment::salt_from_secret(salt),
+            })
+            .send()
+    }
+
+    /// Reveals with the weight of the allowlist plugin, refreshing the voter
+    /// weight record in the same transaction
+    pub fn reveal_vote_with_plugin(
+        &self,
+        name: &str,
+        title: &str,
+        choice: VoteChoice,
+        salt: &str,
+    ) -> Result<Signature, ClientError> {
+        let voter_weight_record_pda = self.get_voter_weight_record_pda(name);
+
+        let update_ixs = self
+            .client
+            .program(allowlist_plugin::ID)?
+            .request()
+            .accounts(allowlist_plugin::accounts::UpdateVoterWeightRecord {
+                registrar: self.get_registrar_pda(name),
+                voter_weight_record: voter_weight_record_pda,
+                voter: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(allowlist_plugin::instruction::UpdateVoterWeightRecord {})
+            .instructions()?;
+
+        let mut request = self.program.request();
+        for ix in update_ixs {
+            request = request.instruction(ix);
+        }
+
+        request
+            .accounts(voting::accounts::RevealVote {
+                governance: self.get_governance_pda(name),
+                proposal: self.get_proposal_pda(name, title),
+                vote_commitment: self.get_vote_pda(name, title),
+                user: self.get_user_pda(name),
+                lockup: None,
+                voter_weight_record: Some(voter_weight_record_pda),
+                voter: self.payer.pubkey(),
+            })
+            .args(voting::instruction::RevealVote {
+                choice,
+                credits: 0,
+                salt: voting::commitment::salt_from_secret(salt),
+            })
+            .send()
+    }
+
+    /// Adds the vote of the NFT `mint` to the commitment of the payer
+    pub fn register_nft_vote(
+        &self,
+        name: &str,
+        title: &str,
+        mint: &Pubkey,
+    ) -> Result<Signature, ClientError> {
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let (nft_vote_pda, _bump) = Pubkey::find_program_address(
+            &[b"nft_vote", proposal_pda.as_ref(), mint.as_ref()],
+            &self.program_id,
+        );
+
+        self.program
+            .request()
+            .accounts(voting::accounts::RegisterNftVote {
+                governance: self.get_governance_pda(name),
+                proposal: proposal_pda,
+                vote_commitment: self.get_vote_pda(name, title),
+                nft_token_account: get_associated_token_address(&self.payer.pubkey(), mint),
+                nft_metadata: Metadata::find_pda(mint).0,
+                nft_vote_record: nft_vote_pda,
+                voter: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::RegisterNftVote {})
+            .send()
+    }
+
+    pub fn cancel_proposal(&self, name: &str, title: &str) -> Result<Signature, ClientError> {
+        self.program
+            .request()
+            .accounts(voting::accounts::CancelProposal {
+                governance: self.get_governance_pda(name),
+                proposal: self.get_proposal_pda(name, title),
+                authority: self.payer.pubkey(),
+            })
+            .args(voting::instruction::CancelProposal {})
+            .send()
+    }
+
+    /// Vetoes a proposal with the payer as the council
+    pub fn veto_proposal(&self, name: &str, title: &str) -> Result<Signature, ClientError> {
+        self.program
+            .request()
+            .accounts(voting::accounts::VetoProposal {
+                governance: self.get_governance_pda(name),
+                proposal: self.get_proposal_pda(name, title),
+                council: self.payer.pubkey(),
+            })
+            .args(voting::instruction::VetoProposal {})
+            .send()
+    }
+
+    /// Attaches `instructions` to `option` as its transaction number `index`
+    pub fn insert_transaction(
+        &self,
+        name: &str,
+        title: &str,
+        option: u8,
+        index: u16,
+        instructions: Vec<Instruction>,
+    ) -> Result<Signature, ClientError> {
+        self.program
+            .request()
+            .accounts(voting::accounts::InsertTransaction {
+                governance: self.get_governance_pda(name),
+                proposal: self.get_proposal_pda(name, title),
+                proposal_transaction: self.get_proposal_transaction_pda(name, title, option, index),
+                authority: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::InsertTransaction {
+                option,
+                index,
+                instructions: instructions.into_iter().map(Into::into).collect(),
+            })
+            .send()
+    }
+
+    /// Executes a transaction, passing the accounts of its instructions
+    pub fn execute_proposal(
+        &self,
+        name: &str,
+        title: &str,
+        option: u8,
+        index: u16,
+    ) -> Result<Signature, ClientError> {
+        let transaction_pda = self.get_proposal_transaction_pda(name, title, option, index);
+        let transaction: ProposalTransaction = self.program.account(transaction_pda)?;
+        let treasury = self.get_treasury_pda(name);
+
+        let mut remaining_accounts = Vec::new();
+        for instruction in &transaction.instructions {
+            remaining_accounts.push(AccountMeta::new_readonly(instruction.program_id, false));
+            for meta in &instruction.accounts {
+                // The treasury signs through the voting program
+                let is_signer = meta.is_signer && meta.pubkey != treasury;
+                remaining_accounts.push(match meta.is_writable {
+                    true => AccountMeta::new(meta.pubkey, is_signer),
+                    false => AccountMeta::new_readonly(meta.pubkey, is_signer),
+                });
+            }
+        }
+
+        self.program
+            .request()
+            .accounts(voting::accounts::ExecuteProposal {
+                governance: self.get_governance_pda(name),
+                proposal: self.get_proposal_pda(name, title),
+                proposal_transaction: transaction_pda,
+                treasury,
+            })
+            .accounts(remaining_accounts)
+            .args(voting::instruction::ExecuteProposal {})
+            .send()
+    }
+
+    pub fn create_council(
+        &self,
+        name: &str,
+        members: Vec<Pubkey>,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let council_pda = self.get_council_pda(name);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::CreateCouncil {
+                governance: governance_pda,
+                council: council_pda,
+                authority: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::CreateCouncil { members })
+            .send()
+    }
+
+    pub fn commit_council_vote(
+        &self,
+        name: &str,
+        title: &str,
+        choice: VoteChoice,
+        salt: &str,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let council_pda = self.get_council_pda(name);
+        let council_vote_pda = self.get_council_vote_pda(name, title);
+
+        let salt = voting::commitment::salt_from_secret(salt);
+        let commitment =
+            voting::commitment::hash_vote(&proposal_pda, &self.payer.pubkey(), choice, 0, &salt);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::CommitCouncilVote {
+                governance: governance_pda,
+                proposal: proposal_pda,
+                council: Some(council_pda),
+                vote_commitment: council_vote_pda,
+                council_mint: None,
+                source: None,
+                escrow: None,
+                voter: self.payer.pubkey(),
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::CommitCouncilVote {
+                commitment,
+                amount: 0,
+            })
+            .send()
+    }
+
+    /// Commits a council vote escrowing `amount` council tokens from `source`
+    #[allow(clippy::too_many_arguments)]
+    pub fn commit_council_vote_with_tokens(
+        &self,
+        name: &str,
+        title: &str,
+        choice: VoteChoice,
+        salt: &str,
+        council_mint: &Pubkey,
+        source: &Pubkey,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let council_vote_pda = self.get_council_vote_pda(name, title);
+        let escrow_pda = self.get_council_escrow_pda(name, title);
+
+        let salt = voting::commitment::salt_from_secret(salt);
+        let commitment =
+            voting::commitment::hash_vote(&proposal_pda, &self.payer.pubkey(), choice, 0, &salt);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::CommitCouncilVote {
+                governance: governance_pda,
+                proposal: proposal_pda,
+                council: None,
+                vote_commitment: council_vote_pda,
+                council_mint: Some(*council_mint),
+                source: Some(*source),
+                escrow: Some(escrow_pda),
+                voter: self.payer.pubkey(),
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::CommitCouncilVote { commitment, amount })
+            .send()
+    }
+
+    pub fn reveal_council_vote(
+        &self,
+        name: &str,
+        title: &str,
+        choice: VoteChoice,
+        salt: &str,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let council_vote_pda = self.get_council_vote_pda(name, title);
+        let escrow_pda = self.get_council_escrow_pda(name, title);
+        let escrow = self
+            .program
+            .rpc()
+            .get_account(&escrow_pda)
+            .ok()
+            .map(|_| escrow_pda);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::RevealCouncilVote {
+                governance: governance_pda,
+                proposal: proposal_pda,
+                vote_commitment: council_vote_pda,
+                escrow,
+                voter: self.payer.pubkey(),
+            })
+            .args(voting::instruction::RevealCouncilVote {
+                choice,
+                salt: voting::commitment::salt_from_secret(salt),
+            })
+            .send()
+    }
+
+    /// Closes a council vote, returning the escrowed council tokens, if any,
+    /// to `destination`
+    pub fn close_council_vote(
+        &self,
+        name: &str,
+        title: &str,
+        destination: Option<Pubkey>,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let council_vote_pda = self.get_council_vote_pda(name, title);
+        let escrow_pda = self.get_council_escrow_pda(name, title);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::CloseCouncilVote {
+                governance: governance_pda,
+                proposal: proposal_pda,
+                vote_commitment: council_vote_pda,
+                escrow: destination.map(|_| escrow_pda),
+                destination,
+                voter: self.payer.pubkey(),
+                token_program: spl_token::ID,
+            })
+            .args(voting::instruction::CloseCouncilVote {})
+            .send()
+    }
+
+    pub fn insert_config_change(
+        &self,
+        name: &str,
+        title: &str,
+        option: u8,
+        config: GovernanceConfig,
+    ) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let config_change_pda = self.get_config_change_pda(name, title);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::InsertConfigChange {
+                governance: governance_pda,
+                proposal: proposal_pda,
+                config_change: config_change_pda,
+                authority: self.payer.pubkey(),
+                system_program: system_program::ID,
+            })
+            .args(voting::instruction::InsertConfigChange { option, config })
+            .send()
+    }
+
+    pub fn execute_config_change(&self, name: &str, title: &str) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let config_change_pda = self.get_config_change_pda(name, title);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::ExecuteConfigChange {
+                governance: governance_pda,
+                proposal: proposal_pda,
+                config_change: config_change_pda,
+            })
+            .args(voting::instruction::ExecuteConfigChange {})
+            .send()
+    }
+
+    pub fn close_vote_commitment(&self, name: &str, title: &str) -> Result<Signature, ClientError> {
+        self.close_vote_commitment_account(name, title, self.get_vote_pda(name, title))
+    }
+
+    /// Closes `vote_pda` as the community vote commitment of the signer
+    pub fn close_vote_commitment_account(
+        &self,
+        name: &str,
+        title: &str,
+        vote_pda: Pubkey,
+    ) -> Result<Signature, ClientError> {
+        let proposal_pda = self.get_proposal_pda(name, title);
+        let user_pda = self.get_user_pda(name);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::CloseVoteCommitment {
+                proposal: proposal_pda,
+                vote_commitment: vote_pda,
+                user: user_pda,
+                voter: self.payer.pubkey(),
+            })
+            .args(voting::instruction::CloseVoteCommitment {})
+            .send()
+    }
+
+    pub fn finalize_proposal(&self, name: &str, title: &str) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::FinalizeProposal {
+                governance: governance_pda,
+                proposal: proposal_pda,
+            })
+            .args(voting::instruction::FinalizeProposal {})
+            .send()
+    }
+
+    pub fn expire_proposal(&self, name: &str, title: &str) -> Result<Signature, ClientError> {
+        let governance_pda = self.get_governance_pda(name);
+        let proposal_pda = self.get_proposal_pda(name, title);
+
+        self.program
+            .request()
+            .accounts(voting::accounts::ExpireProposal {
+                governance: governance_pda,
+                proposal: proposal_pda,
             })
+            .args(voting::instruction::ExpireProposal {})
             .send()
     }
 }
@@ -1,6 +1,16 @@
 use std::thread::sleep;
 
+use allowlist_plugin::AllowlistEntry;
+use anchor_client::solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, signature::Keypair, signer::Signer,
+};
+use anchor_spl::{associated_token::get_associated_token_address, token::spl_token};
 use chrono::Utc;
+use solana_program::{pubkey::Pubkey, system_instruction};
+use voting::{
+    merkle, ApprovalThreshold, Chamber, CouncilMembership, Governance, GovernanceConfig, Lockup,
+    Proposal, ProposalState, Quorum, User, VoteChoice, VotingErrorCode, VotingMode,
+};
 
 use crate::TestSetup;
 
@@ -19,6 +29,42 @@ fn test_create_governance() {
     assert!(fail_res.is_err());
 }
 
+#[test]
+fn test_update_governance_config() {
+    let setup = TestSetup::new();
+    let name = "superteam_config";
+
+    let _ = setup.create_governance(name);
+
+    let config = GovernanceConfig {
+        quorum: Quorum::Percentage(2_000),
+        approval_threshold: ApprovalThreshold::Supermajority(6_667),
+        ..GovernanceConfig::default()
+    };
+
+    // Success pattern
+    let success_res = setup.update_governance_config(name, config.clone());
+    assert!(success_res.is_ok());
+
+    let governance: Governance = setup
+        .program
+        .account(setup.get_governance_pda(name))
+        .unwrap();
+    assert_eq!(governance.authority, setup.payer.pubkey());
+    assert_eq!(governance.config, config);
+
+    // Fail pattern (Invalid config)
+    let fail_res = setup.update_governance_config(
+        name,
+        GovernanceConfig {
+            min_voting_period: 10,
+            max_voting_period: 5,
+            ..GovernanceConfig::default()
+        },
+    );
+    assert!(fail_res.is_err());
+}
+
 #[test]
 fn test_join() {
     let setup = TestSetup::new();
@@ -30,6 +76,12 @@ fn test_join() {
     let success_res = setup.join(name);
     assert!(success_res.is_ok());
 
+    let governance: Governance = setup
+        .program
+        .account(setup.get_governance_pda(name))
+        .unwrap();
+    assert_eq!(governance.member_count, 1);
+
     // Fail pattern (already joined)
     let fail_res = setup.join(name);
     assert!(fail_res.is_err());
@@ -53,6 +105,36 @@ fn test_create_proposal() {
     assert!(fail_res.is_err());
 }
 
+#[test]
+fn test_non_member() {
+    let setup = TestSetup::new();
+    let name = "superteam_members";
+    let title = "Hello World";
+    let member_title = "Hello Members";
+    let end = Utc::now() + chrono::Duration::days(1);
+
+    let _ = setup.create_governance(name);
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, member_title);
+    let _ = setup.start_vote(name, member_title, end.timestamp());
+
+    let outsider = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&outsider.payer.pubkey(), LAMPORTS_PER_SOL);
+
+    // Fail pattern (Not a member)
+    let fail_res = outsider.create_proposal(name, title);
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Not a member)
+    let fail_res = outsider.commit_vote(name, member_title, VoteChoice::Option(0), "salt");
+    assert!(fail_res.is_err());
+
+    // Success pattern (After joining)
+    let _ = outsider.join(name);
+    let success_res = outsider.commit_vote(name, member_title, VoteChoice::Option(0), "salt");
+    assert!(success_res.is_ok());
+}
+
 #[test]
 fn test_start_vote() {
     let setup = TestSetup::new();
@@ -64,13 +146,82 @@ fn test_start_vote() {
     let _ = setup.join(name);
     let _ = setup.create_proposal(name, title);
 
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Draft);
+
     // Success pattern
     let success_res = setup.start_vote(name, title, end.timestamp());
     assert!(success_res.is_ok());
 
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Voting);
+
     // Fail pattern (Does not exist the governance)
     let fail_res = setup.start_vote("fake name", title, end.timestamp());
     assert!(fail_res.is_err());
+
+    // Fail pattern (Already started)
+    let fail_res = setup.start_vote(name, title, end.timestamp());
+    assert!(fail_res.is_err());
+}
+
+#[test]
+fn test_start_vote_validation() {
+    let setup = TestSetup::new();
+    let name = "superteam_start";
+    let title = "Hello World";
+    let now = Utc::now();
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            min_voting_period: 60,
+            max_voting_period: 60 * 60,
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title);
+
+    // Fail pattern (End in the past)
+    let fail_res = setup.start_vote(name, title, (now - chrono::Duration::hours(1)).timestamp());
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Shorter than the minimum voting period)
+    let fail_res = setup.start_vote(
+        name,
+        title,
+        (now + chrono::Duration::seconds(10)).timestamp(),
+    );
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Longer than the maximum voting period)
+    let fail_res = setup.start_vote(name, title, (now + chrono::Duration::days(1)).timestamp());
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Neither the creator nor the governance authority)
+    let other = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&other.payer.pubkey(), LAMPORTS_PER_SOL);
+    let fail_res = other.start_vote(
+        name,
+        title,
+        (now + chrono::Duration::minutes(30)).timestamp(),
+    );
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.start_vote(
+        name,
+        title,
+        (now + chrono::Duration::minutes(30)).timestamp(),
+    );
+    assert!(success_res.is_ok());
 }
 
 #[test]
@@ -79,7 +230,7 @@ fn test_commit_vote() {
     let name = "superteam4";
     let title = "Hello World2";
     let end = Utc::now() + chrono::Duration::days(1);
-    let vote = 1; // Yes
+    let choice = VoteChoice::Option(0); // Yes
     let salt = "salt";
 
     let _ = setup.create_governance(name);
@@ -88,11 +239,11 @@ fn test_commit_vote() {
     let _ = setup.start_vote(name, title, end.timestamp());
 
     // Success pattern
-    let success_res = setup.commit_vote(name, title, vote, salt);
+    let success_res = setup.commit_vote(name, title, choice, salt);
     assert!(success_res.is_ok());
 
     // Fail pattern (Already committed)
-    let fail_res = setup.commit_vote(name, title, vote, salt);
+    let fail_res = setup.commit_vote(name, title, choice, salt);
     assert!(fail_res.is_err());
 }
 
@@ -102,22 +253,1768 @@ fn test_reveal_vote() {
     let name = "superteam5";
     let title = "Hello World3";
     let end = Utc::now().timestamp() + 1;
-    let vote = 1; // Yes
+    let choice = VoteChoice::Option(0); // Yes
     let salt = "salt";
 
     let _ = setup.create_governance(name);
     let _ = setup.join(name);
     let _ = setup.create_proposal(name, title);
     let _ = setup.start_vote(name, title, end);
-    let _ = setup.commit_vote(name, title, vote, salt);
+    let _ = setup.commit_vote(name, title, choice, salt);
 
     sleep(std::time::Duration::new(1, 0));
 
     // Success pattern
-    let success_res = setup.reveal_vote(name, title, vote, salt);
+    let success_res = setup.reveal_vote(name, title, choice, salt);
     assert!(success_res.is_ok());
 
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Revealing);
+
     // Fail pattern (Governance does not exist)
-    let fail_res = setup.reveal_vote("fake name", title, vote, salt);
+    let fail_res = setup.reveal_vote("fake name", title, choice, salt);
+    assert!(fail_res.is_err());
+}
+
+#[test]
+fn test_reveal_vote_twice() {
+    let setup = TestSetup::new();
+    let name = "superteam_reveal_twice";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    // Fail pattern (Close before the proposal is finalized)
+    let fail_res = setup.close_vote_commitment(name, title);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(1, 0));
+
+    // Success pattern
+    let success_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(success_res.is_ok());
+
+    // Fail pattern (Already revealed)
+    let fail_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(fail_res.is_err());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.tallies, vec![1, 0]);
+
+    // Fail pattern (Close before the proposal is finalized)
+    let fail_res = setup.close_vote_commitment(name, title);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+
+    // Success pattern (Rent refunded to the voter)
+    let success_res = setup.close_vote_commitment(name, title);
+    assert!(success_res.is_ok());
+
+    // Fail pattern (Commitment consumed)
+    let fail_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(fail_res.is_err());
+}
+
+#[test]
+fn test_reveal_vote_binding() {
+    let setup = TestSetup::new();
+    let name = "superteam_binding";
+    let title_a = "Proposal A";
+    let title_b = "Proposal B";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let _ = setup.create_governance(name);
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title_a);
+    let _ = setup.create_proposal(name, title_b);
+    let _ = setup.start_vote(name, title_a, end);
+    let _ = setup.start_vote(name, title_b, end);
+    let _ = setup.commit_vote(name, title_a, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+
+    // Fail pattern (Commitment of proposal A revealed against proposal B)
+    let fail_res = setup
+        .program
+        .request()
+        .accounts(voting::accounts::RevealVote {
+            governance: setup.get_governance_pda(name),
+            proposal: setup.get_proposal_pda(name, title_b),
+            vote_commitment: setup.get_vote_pda(name, title_a),
+            user: setup.get_user_pda(name),
+            lockup: None,
+            voter_weight_record: None,
+            voter: setup.payer.pubkey(),
+        })
+        .args(voting::instruction::RevealVote {
+            choice: VoteChoice::Option(0),
+            credits: 0,
+            salt: voting::commitment::salt_from_secret(salt),
+        })
+        .send();
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.reveal_vote(name, title_a, choice, salt);
+    assert!(success_res.is_ok());
+}
+
+#[test]
+fn test_commitment_encoding() {
+    let proposal = Pubkey::new_unique();
+    let other_proposal = Pubkey::new_unique();
+    let voter = Pubkey::new_unique();
+    let other_voter = Pubkey::new_unique();
+    let salt = voting::commitment::salt_from_secret("salt");
+
+    let commitment =
+        voting::commitment::hash_vote(&proposal, &voter, VoteChoice::Option(0), 0, &salt);
+
+    // Deterministic
+    assert_eq!(
+        commitment,
+        voting::commitment::hash_vote(&proposal, &voter, VoteChoice::Option(0), 0, &salt)
+    );
+
+    // Bound to the vote, the credits, the proposal, the voter and the salt
+    assert_ne!(
+        commitment,
+        voting::commitment::hash_vote(&proposal, &voter, VoteChoice::Option(0), 49, &salt)
+    );
+    assert_ne!(
+        commitment,
+        voting::commitment::hash_vote(&proposal, &voter, VoteChoice::Option(1), 0, &salt)
+    );
+    assert_ne!(
+        commitment,
+        voting::commitment::hash_vote(&proposal, &voter, VoteChoice::Abstain, 0, &salt)
+    );
+    assert_ne!(
+        commitment,
+        voting::commitment::hash_vote(&other_proposal, &voter, VoteChoice::Option(0), 0, &salt)
+    );
+    assert_ne!(
+        commitment,
+        voting::commitment::hash_vote(&proposal, &other_voter, VoteChoice::Option(0), 0, &salt)
+    );
+    assert_ne!(
+        commitment,
+        voting::commitment::hash_vote(
+            &proposal,
+            &voter,
+            VoteChoice::Option(0),
+            0,
+            &voting::commitment::salt_from_secret("pepper")
+        )
+    );
+}
+
+#[test]
+fn test_finalize_proposal() {
+    let setup = TestSetup::new();
+    let name = "superteam_finalize";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    // Fail pattern (Voting not ended)
+    let fail_res = setup.finalize_proposal(name, title);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_vote(name, title, choice, salt);
+
+    // Fail pattern (Reveal period not ended)
+    let fail_res = setup.finalize_proposal(name, title);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(3, 0));
+
+    // Success pattern
+    let success_res = setup.finalize_proposal(name, title);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Succeeded);
+
+    // Nothing to execute
+    assert_eq!(
+        proposal.current_state(Utc::now().timestamp()),
+        ProposalState::Executed
+    );
+
+    // Fail pattern (Already finalized)
+    let fail_res = setup.finalize_proposal(name, title);
+    assert!(fail_res.is_err());
+}
+
+#[test]
+fn test_reveal_after_reveal_period() {
+    let setup = TestSetup::new();
+    let name = "superteam_reveal_late";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 1,
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(3, 0));
+
+    // Revealing without any reveal
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Voting);
+    assert_eq!(
+        proposal.current_state(Utc::now().timestamp()),
+        ProposalState::Revealing
+    );
+
+    // Fail pattern (Reveal period ended)
+    let fail_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(fail_res.is_err());
+
+    // Success pattern (Finalized without any reveal)
+    let success_res = setup.finalize_proposal(name, title);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Defeated);
+}
+
+#[test]
+fn test_approval_rules() {
+    let config = GovernanceConfig::default();
+
+    // Simple majority with an absolute quorum of 1
+    assert_eq!(config.winning_option(10, &[1, 0], 0), Some(0));
+    assert_eq!(config.winning_option(10, &[1, 2], 0), Some(1));
+    assert_eq!(config.winning_option(10, &[1, 1], 0), None);
+    assert_eq!(config.winning_option(10, &[0, 0], 0), None);
+
+    // Plurality among several options
+    assert_eq!(config.winning_option(10, &[1, 3, 2, 0, 2], 0), Some(1));
+    assert_eq!(config.winning_option(10, &[1, 3, 3, 0, 2], 0), None);
+
+    // Quorum as a share of the members
+    let config = GovernanceConfig {
+        quorum: Quorum::Percentage(5_000),
+        ..GovernanceConfig::default()
+    };
+    assert_eq!(config.winning_option(10, &[4, 1], 0), Some(0));
+    assert_eq!(config.winning_option(10, &[4, 0], 0), None);
+    assert_eq!(config.winning_option(0, &[0, 0], 0), None);
+
+    // Abstentions count toward the quorum
+    assert_eq!(config.winning_option(10, &[4, 0], 1), Some(0));
+    assert_eq!(config.winning_option(10, &[0, 0], 5), None);
+
+    // Two-thirds supermajority of all the votes
+    let config = GovernanceConfig {
+        approval_threshold: ApprovalThreshold::Supermajority(6_667),
+        ..GovernanceConfig::default()
+    };
+    assert_eq!(config.winning_option(10, &[3, 1], 0), Some(0));
+    assert_eq!(config.winning_option(10, &[2, 1], 0), None);
+    assert_eq!(config.winning_option(10, &[4, 1, 1], 0), None);
+
+    // Abstentions do not count toward the threshold
+    assert_eq!(config.winning_option(10, &[3, 1], 5), Some(0));
+
+    // Invalid rules
+    assert!(GovernanceConfig {
+        quorum: Quorum::Percentage(10_001),
+        ..GovernanceConfig::default()
+    }
+    .validate()
+    .is_err());
+    assert!(GovernanceConfig {
+        approval_threshold: ApprovalThreshold::Supermajority(5_000),
+        ..GovernanceConfig::default()
+    }
+    .validate()
+    .is_err());
+}
+
+#[test]
+fn test_multi_option_proposal() {
+    let setup = TestSetup::new();
+    let name = "superteam_vendors";
+    let title = "Pick a vendor";
+    let vendors = ["Alpha", "Bravo", "Charlie", "Delta", "Echo"];
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(3); // Delta
+    let salt = "salt";
+
+    let _ = setup.create_governance(name);
+    let _ = setup.join(name);
+
+    // Fail pattern (Single option)
+    let fail_res = setup.create_proposal_with_options(name, title, &["Alpha"]);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.create_proposal_with_options(name, title, &vendors);
+    assert!(success_res.is_ok());
+
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+
+    // Fail pattern (Revealed with another option)
+    let fail_res = setup.reveal_vote(name, title, VoteChoice::Option(1), salt);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.options, vendors);
+    assert_eq!(proposal.tallies, vec![0, 0, 0, 1, 0]);
+}
+
+#[test]
+fn test_reveal_invalid_option() {
+    let setup = TestSetup::new();
+    let name = "superteam_invalid_option";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(7); // Out of range
+    let salt = "salt";
+
+    let _ = setup.create_governance(name);
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+
+    // Fail pattern (Option out of range)
+    let fail_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(fail_res.is_err());
+}
+
+#[test]
+fn test_abstain() {
+    let setup = TestSetup::new();
+    let name = "superteam_abstain";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Abstain;
+    let salt = "salt";
+
+    let _ = setup.create_governance(name);
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+
+    // Success pattern
+    let success_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.tallies, vec![0, 0]);
+    assert_eq!(proposal.votes_abstain, 1);
+}
+
+#[test]
+fn test_token_weighted_vote() {
+    let setup = TestSetup::new();
+    let name = "superteam_tokens";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 2;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let mint = Keypair::new();
+    let source = Keypair::new();
+    let _ = setup.create_mint(&mint);
+    let _ = setup.create_token_account(&source, &mint.pubkey(), 1_000);
+
+    // Fail pattern (Token weighted without a community mint)
+    let fail_res = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            voting_mode: VotingMode::TokenWeighted,
+            ..GovernanceConfig::default()
+        },
+    );
+    assert!(fail_res.is_err());
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            voting_mode: VotingMode::TokenWeighted,
+            community_mint: Some(mint.pubkey()),
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+
+    // Success pattern
+    let success_res = setup.deposit_governing_tokens(name, &mint.pubkey(), &source.pubkey(), 600);
+    assert!(success_res.is_ok());
+
+    let user: User = setup.program.account(setup.get_user_pda(name)).unwrap();
+    assert_eq!(user.deposited_amount, 600);
+
+    let governance: Governance = setup
+        .program
+        .account(setup.get_governance_pda(name))
+        .unwrap();
+    assert_eq!(governance.total_deposited, 600);
+
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(2, 0));
+
+    // Success pattern (Weighted by the deposit)
+    let success_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.tallies, vec![600, 0]);
+    assert_eq!(proposal.max_vote_weight, 600);
+}
+
+#[test]
+fn test_withdraw_governing_tokens() {
+    let setup = TestSetup::new();
+    let name = "superteam_withdraw";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let mint = Keypair::new();
+    let source = Keypair::new();
+    let _ = setup.create_mint(&mint);
+    let _ = setup.create_token_account(&source, &mint.pubkey(), 1_000);
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            voting_mode: VotingMode::TokenWeighted,
+            community_mint: Some(mint.pubkey()),
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = setup.deposit_governing_tokens(name, &mint.pubkey(), &source.pubkey(), 600);
+
+    // Success pattern
+    let success_res = setup.withdraw_governing_tokens(name, &source.pubkey(), 100);
+    assert!(success_res.is_ok());
+
+    // Fail pattern (More than the deposit)
+    let fail_res = setup.withdraw_governing_tokens(name, &source.pubkey(), 501);
+    assert!(fail_res.is_err());
+
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    // Fail pattern (Locked by an unrevealed vote)
+    let fail_res = setup.withdraw_governing_tokens(name, &source.pubkey(), 100);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_vote(name, title, choice, salt);
+
+    // Fail pattern (Locked until the proposal is finalized)
+    let fail_res = setup.withdraw_governing_tokens(name, &source.pubkey(), 100);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+    let _ = setup.close_vote_commitment(name, title);
+
+    // Success pattern (Lock released)
+    let success_res = setup.withdraw_governing_tokens(name, &source.pubkey(), 500);
+    assert!(success_res.is_ok());
+
+    let user: User = setup.program.account(setup.get_user_pda(name)).unwrap();
+    assert_eq!(user.deposited_amount, 0);
+    assert_eq!(user.active_votes, 0);
+}
+
+#[test]
+fn test_quadratic_vote() {
+    let setup = TestSetup::new();
+    let name = "superteam_quadratic";
+    let title_a = "Proposal A";
+    let title_b = "Proposal B";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let mint = Keypair::new();
+    let source = Keypair::new();
+    let _ = setup.create_mint(&mint);
+    let _ = setup.create_token_account(&source, &mint.pubkey(), 1_000);
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            voting_mode: VotingMode::Quadratic,
+            community_mint: Some(mint.pubkey()),
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = setup.deposit_governing_tokens(name, &mint.pubkey(), &source.pubkey(), 100);
+
+    let _ = setup.create_proposal(name, title_a);
+    let _ = setup.start_vote(name, title_a, end);
+    let _ = setup.commit_vote_with_credits(name, title_a, choice, 50, salt);
+
+    let _ = setup.create_proposal(name, title_b);
+    let _ = setup.start_vote(name, title_b, end);
+    let _ = setup.commit_vote_with_credits(name, title_b, choice, 60, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+
+    // Fail pattern (Credits not matching the commitment)
+    let fail_res = setup.reveal_vote_with_credits(name, title_a, choice, 100, salt);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.reveal_vote_with_credits(name, title_a, choice, 50, salt);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title_a))
+        .unwrap();
+    assert_eq!(proposal.tallies, vec![7, 0]);
+    assert_eq!(proposal.max_vote_weight, 10);
+
+    let user: User = setup.program.account(setup.get_user_pda(name)).unwrap();
+    assert_eq!(user.credits_spent, 50);
+
+    // Fail pattern (More credits than left of the deposit)
+    let fail_res = setup.reveal_vote_with_credits(name, title_b, choice, 60, salt);
+    assert!(fail_res.is_err());
+}
+
+#[test]
+fn test_vote_escrow() {
+    let setup = TestSetup::new();
+    let name = "superteam_escrow";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let mint = Keypair::new();
+    let source = Keypair::new();
+    let _ = setup.create_mint(&mint);
+    let _ = setup.create_token_account(&source, &mint.pubkey(), 1_000);
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            voting_mode: VotingMode::VoteEscrow,
+            community_mint: Some(mint.pubkey()),
+            max_lockup_period: 100,
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = setup.deposit_governing_tokens(name, &mint.pubkey(), &source.pubkey(), 1_000);
+
+    // Fail pattern (Longer than the maximum lockup period)
+    let fail_res = setup.create_lockup(name, 1_000, 101);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.create_lockup(name, 1_000, 50);
+    assert!(success_res.is_ok());
+
+    let user: User = setup.program.account(setup.get_user_pda(name)).unwrap();
+    assert_eq!(user.deposited_amount, 0);
+
+    // Fail pattern (Unlock moved earlier)
+    let fail_res = setup.extend_lockup(name, 10);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.extend_lockup(name, 100);
+    assert!(success_res.is_ok());
+
+    // Fail pattern (Lockup not expired)
+    let fail_res = setup.unlock(name);
+    assert!(fail_res.is_err());
+
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+
+    // Success pattern (Decayed voting power)
+    let success_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert!(proposal.tallies[0] > 900 && proposal.tallies[0] < 1_000);
+}
+
+#[test]
+fn test_lockup_weight() {
+    let lockup = Lockup {
+        user: Pubkey::new_unique(),
+        amount: 1_000,
+        unlock_at: 100,
+    };
+
+    // Full power at the maximum duration, decaying linearly to zero
+    assert_eq!(lockup.vote_weight(0, 100), 1_000);
+    assert_eq!(lockup.vote_weight(0, 50), 1_000);
+    assert_eq!(lockup.vote_weight(25, 100), 750);
+    assert_eq!(lockup.vote_weight(99, 100), 10);
+    assert_eq!(lockup.vote_weight(100, 100), 0);
+    assert_eq!(lockup.vote_weight(150, 100), 0);
+}
+
+#[test]
+fn test_delegation() {
+    let setup = TestSetup::new();
+    let name = "superteam_delegation";
+    let title = "Hello World";
+    let second_title = "Second proposal";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let delegate = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&delegate.payer.pubkey(), LAMPORTS_PER_SOL);
+
+    let _ = setup.create_governance(name);
+    let _ = setup.join(name);
+    let _ = delegate.join(name);
+
+    // Fail pattern (Self delegation)
+    let fail_res = setup.delegate(name, &setup.payer.pubkey());
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.delegate(name, &delegate.payer.pubkey());
+    assert!(success_res.is_ok());
+
+    let user: User = setup.program.account(setup.get_user_pda(name)).unwrap();
+    assert_eq!(user.delegate, Some(delegate.get_user_pda(name)));
+
+    // Fail pattern (Cycle)
+    let fail_res = delegate.delegate(name, &setup.payer.pubkey());
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Vote escrow mode)
+    let escrow_name = "superteam_delegation_escrow";
+    let community_mint = Keypair::new();
+    let _ = setup.create_mint(&community_mint);
+    let _ = setup.create_governance_with_config(
+        escrow_name,
+        GovernanceConfig {
+            voting_mode: VotingMode::VoteEscrow,
+            community_mint: Some(community_mint.pubkey()),
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(escrow_name);
+    let _ = delegate.join(escrow_name);
+    let fail_res = setup.delegate(escrow_name, &delegate.payer.pubkey());
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Quadratic mode)
+    let quadratic_name = "superteam_delegation_quadratic";
+    let _ = setup.create_governance_with_config(
+        quadratic_name,
+        GovernanceConfig {
+            voting_mode: VotingMode::Quadratic,
+            community_mint: Some(community_mint.pubkey()),
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(quadratic_name);
+    let _ = delegate.join(quadratic_name);
+    let fail_res = setup.delegate(quadratic_name, &delegate.payer.pubkey());
+    assert!(fail_res.is_err());
+
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.create_proposal(name, second_title);
+    let _ = setup.start_vote(name, second_title, end);
+
+    // Fail pattern (Voting power delegated)
+    let fail_res = setup.commit_vote(name, title, choice, salt);
+    assert!(fail_res.is_err());
+
+    let _ = delegate.commit_vote(name, title, choice, salt);
+    let _ = delegate.commit_vote(name, second_title, choice, salt);
+
+    // Fail pattern (Delegate has an active vote)
+    let fail_res = setup.undelegate(name);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(1, 0));
+
+    // Success pattern (Delegated weight aggregated)
+    let success_res = delegate.reveal_vote(name, title, choice, salt);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.tallies, vec![2, 0]);
+
+    // Success pattern (Same delegator on a concurrent proposal)
+    let success_res = delegate.reveal_vote(name, second_title, choice, salt);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, second_title))
+        .unwrap();
+    assert_eq!(proposal.tallies, vec![2, 0]);
+}
+
+#[test]
+fn test_voter_weight_plugin() {
+    let setup = TestSetup::new();
+    let name = "superteam_plugin";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let outsider = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&outsider.payer.pubkey(), LAMPORTS_PER_SOL);
+
+    // Fail pattern (Percentage quorum with a plugin)
+    let fail_res = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            quorum: Quorum::Percentage(5_000),
+            voter_weight_plugin: Some(allowlist_plugin::ID),
+            ..GovernanceConfig::default()
+        },
+    );
+    assert!(fail_res.is_err());
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            voter_weight_plugin: Some(allowlist_plugin::ID),
+            ..GovernanceConfig::default()
+        },
+    );
+
+    // Fail pattern (Not the governance authority)
+    let fail_res = outsider.create_registrar(
+        name,
+        vec![AllowlistEntry {
+            voter: outsider.payer.pubkey(),
+            weight: 1_000,
+        }],
+    );
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.create_registrar(
+        name,
+        vec![AllowlistEntry {
+            voter: setup.payer.pubkey(),
+            weight: 42,
+        }],
+    );
+    assert!(success_res.is_ok());
+    let _ = setup.join(name);
+    let _ = outsider.join(name);
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+    let _ = outsider.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+
+    // Fail pattern (Missing voter weight record)
+    let fail_res = setup.reveal_vote(name, title, choice, salt);
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Not on the allowlist)
+    let fail_res = outsider.reveal_vote_with_plugin(name, title, choice, salt);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.reveal_vote_with_plugin(name, title, choice, salt);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.tallies, vec![42, 0]);
+}
+
+#[test]
+fn test_nft_vote() {
+    let setup = TestSetup::new();
+    let name = "superteam_nft";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 4;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let collection = Keypair::new();
+    let nft = Keypair::new();
+    let second_nft = Keypair::new();
+    let _ = setup.create_nft(&collection, None);
+    let _ = setup.create_nft(&nft, Some(&collection.pubkey()));
+    let _ = setup.create_nft(&second_nft, Some(&collection.pubkey()));
+
+    // Fail pattern (Unverified collection)
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            voting_mode: VotingMode::NftWeighted,
+            nft_collection: Some(collection.pubkey()),
+            ..GovernanceConfig::default()
+        },
+    );
+    let fail_res = setup.join_with_nft(name, &nft.pubkey());
+    assert!(fail_res.is_err());
+
+    let _ = setup.verify_collection(&nft.pubkey(), &collection.pubkey());
+    let _ = setup.verify_collection(&second_nft.pubkey(), &collection.pubkey());
+
+    // Fail pattern (No NFT)
+    let fail_res = setup.join(name);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.join_with_nft(name, &nft.pubkey());
+    assert!(success_res.is_ok());
+
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    // Success pattern
+    let success_res = setup.register_nft_vote(name, title, &nft.pubkey());
+    assert!(success_res.is_ok());
+
+    // Fail pattern (Same NFT twice)
+    let fail_res = setup.register_nft_vote(name, title, &nft.pubkey());
+    assert!(fail_res.is_err());
+
+    let holder = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&holder.payer.pubkey(), LAMPORTS_PER_SOL);
+    for mint in [nft.pubkey(), second_nft.pubkey()] {
+        let _ = setup.create_associated_token_account(&holder.payer.pubkey(), &mint);
+        let transfer = spl_token::instruction::transfer(
+            &spl_token::ID,
+            &get_associated_token_address(&setup.payer.pubkey(), &mint),
+            &get_associated_token_address(&holder.payer.pubkey(), &mint),
+            &setup.payer.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap();
+        let _ = setup.program.request().instruction(transfer).send();
+    }
+
+    // Fail pattern (NFT already backs a member)
+    let fail_res = holder.join_with_nft(name, &nft.pubkey());
+    assert!(fail_res.is_err());
+
+    let _ = holder.join_with_nft(name, &second_nft.pubkey());
+    let _ = holder.commit_vote(name, title, choice, salt);
+
+    // Fail pattern (NFT already counted on the proposal by another member)
+    let fail_res = holder.register_nft_vote(name, title, &nft.pubkey());
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(4, 0));
+
+    let _ = setup.reveal_vote(name, title, choice, salt);
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.tallies, vec![1, 0]);
+}
+
+#[test]
+fn test_membership_snapshot() {
+    let setup = TestSetup::new();
+    let name = "superteam_snapshot";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let outsider = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&outsider.payer.pubkey(), LAMPORTS_PER_SOL);
+
+    let members = [
+        (setup.payer.pubkey(), 3),
+        (Pubkey::new_unique(), 1),
+        (Pubkey::new_unique(), 1),
+    ];
+    let levels = merkle::build_tree(
+        members
+            .iter()
+            .map(|(wallet, weight)| merkle::hash_member(wallet, *weight))
+            .collect(),
+    );
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            membership_root: merkle::root(&levels),
+            ..GovernanceConfig::default()
+        },
+    );
+
+    // Fail pattern (No proof)
+    let fail_res = setup.join(name);
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Not in the snapshot)
+    let fail_res = outsider.join_with_proof(name, 3, merkle::proof(&levels, 0));
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Wrong weight)
+    let fail_res = setup.join_with_proof(name, 4, merkle::proof(&levels, 0));
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.join_with_proof(name, 3, merkle::proof(&levels, 0));
+    assert!(success_res.is_ok());
+
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+
+    let _ = setup.reveal_vote(name, title, choice, salt);
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.tallies, vec![3, 0]);
+}
+
+#[test]
+fn test_merkle_proof() {
+    let members = (0..5)
+        .map(|weight| (Pubkey::new_unique(), weight))
+        .collect::<Vec<_>>();
+    let leaves = members
+        .iter()
+        .map(|(wallet, weight)| merkle::hash_member(wallet, *weight))
+        .collect::<Vec<_>>();
+    let levels = merkle::build_tree(leaves.clone());
+    let root = merkle::root(&levels).unwrap();
+
+    // Every member, including the one without a sibling
+    for (index, leaf) in leaves.iter().enumerate() {
+        assert!(merkle::verify(&root, *leaf, &merkle::proof(&levels, index)));
+    }
+
+    // Another weight or another proof
+    let (wallet, weight) = members[1];
+    let proof = merkle::proof(&levels, 1);
+    assert!(!merkle::verify(
+        &root,
+        merkle::hash_member(&wallet, weight + 1),
+        &proof
+    ));
+    assert!(!merkle::verify(
+        &root,
+        leaves[1],
+        &merkle::proof(&levels, 2)
+    ));
+
+    // Single member
+    let levels = merkle::build_tree(vec![leaves[0]]);
+    assert_eq!(merkle::root(&levels), Some(leaves[0]));
+    assert!(merkle::proof(&levels, 0).is_empty());
+    assert_eq!(merkle::root(&merkle::build_tree(vec![])), None);
+}
+
+#[test]
+fn test_execute_proposal() {
+    let setup = TestSetup::new();
+    let name = "superteam_execute";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let outsider = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&outsider.payer.pubkey(), LAMPORTS_PER_SOL);
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title);
+
+    let treasury = setup.get_treasury_pda(name);
+    let recipient = Pubkey::new_unique();
+    let _ = setup.fund(&treasury, LAMPORTS_PER_SOL);
+    let transfer = system_instruction::transfer(&treasury, &recipient, LAMPORTS_PER_SOL / 2);
+
+    // Fail pattern (Not the creator)
+    let fail_res = outsider.insert_transaction(name, title, 0, 0, vec![transfer.clone()]);
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Index out of order)
+    let fail_res = setup.insert_transaction(name, title, 0, 1, vec![transfer.clone()]);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.insert_transaction(name, title, 0, 0, vec![transfer.clone()]);
+    assert!(success_res.is_ok());
+
+    let _ = setup.start_vote(name, title, end);
+
+    // Fail pattern (Not a draft)
+    let fail_res = setup.insert_transaction(name, title, 0, 1, vec![transfer]);
+    assert!(fail_res.is_err());
+
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    // Fail pattern (Not succeeded)
+    let fail_res = setup.execute_proposal(name, title, 0, 0);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_vote(name, title, choice, salt);
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+
+    // Success pattern
+    let success_res = setup.execute_proposal(name, title, 0, 0);
+    assert!(success_res.is_ok());
+
+    let balance = setup.program.rpc().get_balance(&recipient).unwrap();
+    assert_eq!(balance, LAMPORTS_PER_SOL / 2);
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Executed);
+
+    // Fail pattern (Already executed)
+    let fail_res = setup.execute_proposal(name, title, 0, 0);
+    assert!(fail_res.is_err());
+}
+
+#[test]
+fn test_cancel_proposal() {
+    let setup = TestSetup::new();
+    let name = "superteam_cancel";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+
+    let outsider = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&outsider.payer.pubkey(), LAMPORTS_PER_SOL);
+
+    let _ = setup.create_governance(name);
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title);
+
+    // Fail pattern (Not the creator)
+    let fail_res = outsider.cancel_proposal(name, title);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.cancel_proposal(name, title);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Cancelled);
+
+    // Fail pattern (Already cancelled)
+    let fail_res = setup.cancel_proposal(name, title);
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Voting on a cancelled proposal)
+    let fail_res = setup.start_vote(name, title, end);
+    assert!(fail_res.is_err());
+}
+
+#[test]
+fn test_veto_proposal() {
+    let setup = TestSetup::new();
+    let name = "superteam_veto";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let council = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&council.payer.pubkey(), LAMPORTS_PER_SOL);
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            council: Some(council.payer.pubkey()),
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = setup.create_proposal(name, title);
+
+    let treasury = setup.get_treasury_pda(name);
+    let _ = setup.fund(&treasury, LAMPORTS_PER_SOL);
+    let transfer =
+        system_instruction::transfer(&treasury, &Pubkey::new_unique(), LAMPORTS_PER_SOL / 2);
+    let _ = setup.insert_transaction(name, title, 0, 0, vec![transfer.clone()]);
+
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_vote(name, title, choice, salt);
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+
+    // Fail pattern (Not the council)
+    let fail_res = setup.veto_proposal(name, title);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = council.veto_proposal(name, title);
+    assert!(success_res.is_ok());
+
+    // Fail pattern (Executing a vetoed proposal)
+    let fail_res = setup.execute_proposal(name, title, 0, 0);
+    assert!(fail_res.is_err());
+
+    let title = "Partly executed";
+    let end = Utc::now().timestamp() + 1;
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.insert_transaction(name, title, 0, 0, vec![transfer.clone()]);
+    let _ = setup.insert_transaction(name, title, 0, 1, vec![transfer]);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_vote(name, title, choice, salt);
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+    let _ = setup.execute_proposal(name, title, 0, 0);
+
+    // Fail pattern (Execution started)
+    let fail_res = council.veto_proposal(name, title);
+    assert!(fail_res.is_err());
+}
+
+#[test]
+fn test_execution_timelock() {
+    let setup = TestSetup::new();
+    let name = "superteam_timelock";
+    let title = "Hello World";
+    let expiring_title = "Expiring";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let config = GovernanceConfig {
+        reveal_period: 2,
+        execution_delay: 3,
+        execution_grace_period: Some(3),
+        ..GovernanceConfig::default()
+    };
+    let _ = setup.create_governance_with_config(name, config.clone());
+    let _ = setup.join(name);
+
+    let treasury = setup.get_treasury_pda(name);
+    let _ = setup.fund(&treasury, LAMPORTS_PER_SOL);
+    let transfer =
+        system_instruction::transfer(&treasury, &Pubkey::new_unique(), LAMPORTS_PER_SOL / 4);
+    for title in [title, expiring_title] {
+        let _ = setup.create_proposal(name, title);
+        let _ = setup.insert_transaction(name, title, 0, 0, vec![transfer.clone()]);
+        let _ = setup.start_vote(name, title, end);
+        let _ = setup.commit_vote(name, title, choice, salt);
+    }
+
+    sleep(std::time::Duration::new(1, 0));
+    for title in [title, expiring_title] {
+        let _ = setup.reveal_vote(name, title, choice, salt);
+    }
+    sleep(std::time::Duration::new(3, 0));
+    for title in [title, expiring_title] {
+        let _ = setup.finalize_proposal(name, title);
+    }
+
+    // The execution window is set at the success of the proposal
+    let _ = setup.update_governance_config(
+        name,
+        GovernanceConfig {
+            execution_delay: 0,
+            execution_grace_period: None,
+            ..config
+        },
+    );
+
+    // Fail pattern (Timelock not elapsed)
+    let fail_res = setup.execute_proposal(name, title, 0, 0);
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Grace period not ended)
+    let fail_res = setup.expire_proposal(name, expiring_title);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(4, 0));
+
+    // Success pattern
+    let success_res = setup.execute_proposal(name, title, 0, 0);
+    assert!(success_res.is_ok());
+
+    sleep(std::time::Duration::new(3, 0));
+
+    // Fail pattern (Grace period ended)
+    let fail_res = setup.execute_proposal(name, expiring_title, 0, 0);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.expire_proposal(name, expiring_title);
+    assert!(success_res.is_ok());
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, expiring_title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Expired);
+}
+
+#[test]
+fn test_execution_window() {
+    let config = GovernanceConfig {
+        execution_delay: 10,
+        ..GovernanceConfig::default()
+    };
+
+    // Executable once the timelock elapsed, without limit
+    assert_eq!(config.execution_window(100), Ok((110, None)));
+
+    // Expired after the grace period
+    let config = GovernanceConfig {
+        execution_grace_period: Some(5),
+        ..config
+    };
+    assert_eq!(config.execution_window(100), Ok((110, Some(115))));
+    assert_eq!(
+        config.execution_window(i64::MAX),
+        Err(VotingErrorCode::Overflow.into())
+    );
+}
+
+#[test]
+fn test_treasury() {
+    let setup = TestSetup::new();
+    let name = "superteam_treasury";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+
+    let treasury = setup.get_treasury_pda(name);
+    let mint = Keypair::new();
+    let recipient = Keypair::new();
+    let treasury_account = get_associated_token_address(&treasury, &mint.pubkey());
+    let _ = setup.create_mint(&mint);
+    let _ = setup.create_associated_token_account(&treasury, &mint.pubkey());
+    let _ = setup.mint_to(&mint.pubkey(), &treasury_account, 1_000);
+    let _ = setup.create_token_account(&recipient, &mint.pubkey(), 0);
+
+    let transfer = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &treasury_account,
+        &recipient.pubkey(),
+        &treasury,
+        &[],
+        400,
+    )
+    .unwrap();
+
+    // Fail pattern (Transfer out without a proposal)
+    let fail_res = setup.program.request().instruction(transfer.clone()).send();
+    assert!(fail_res.is_err());
+
+    let _ = setup.create_proposal(name, title);
+    let _ = setup.insert_transaction(name, title, 0, 0, vec![transfer]);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_vote(name, title, choice, salt);
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+
+    // Success pattern
+    let success_res = setup.execute_proposal(name, title, 0, 0);
+    assert!(success_res.is_ok());
+
+    let rpc = setup.program.rpc();
+    let balance = |account| {
+        rpc.get_token_account_balance(account)
+            .unwrap()
+            .amount
+            .parse::<u64>()
+            .unwrap()
+    };
+    assert_eq!(balance(&treasury_account), 600);
+    assert_eq!(balance(&recipient.pubkey()), 400);
+}
+
+#[test]
+fn test_council_chamber() {
+    let setup = TestSetup::new();
+    let name = "superteam_council";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let outsider = TestSetup::new_with_payer(Keypair::new());
+    let _ = setup.fund(&outsider.payer.pubkey(), LAMPORTS_PER_SOL);
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            council_chamber: Some(CouncilMembership::Members),
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+    let _ = outsider.join(name);
+
+    // Fail pattern (Not the governance authority)
+    let fail_res = outsider.create_council(name, vec![outsider.payer.pubkey()]);
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Duplicate member)
+    let fail_res = setup.create_council(name, vec![setup.payer.pubkey(), setup.payer.pubkey()]);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.create_council(name, vec![setup.payer.pubkey()]);
+    assert!(success_res.is_ok());
+
+    let options = ["Yes", "No"];
+    let _ = setup.create_proposal_in_chamber(name, title, &options, Chamber::Both);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    // Fail pattern (Not a council member)
+    let fail_res = outsider.commit_council_vote(name, title, choice, salt);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.commit_council_vote(name, title, choice, salt);
+    assert!(success_res.is_ok());
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_vote(name, title, choice, salt);
+
+    // Fail pattern (Commitment mismatch)
+    let fail_res = setup.reveal_council_vote(name, title, VoteChoice::Option(1), salt);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.reveal_council_vote(name, title, choice, salt);
+    assert!(success_res.is_ok());
+
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.council_tallies, vec![1, 0]);
+    assert_eq!(proposal.council_max_vote_weight, 1);
+    assert_eq!(proposal.state, ProposalState::Succeeded);
+
+    // Fail pattern (Council vote closed as a community vote)
+    let council_vote = setup.get_council_vote_pda(name, title);
+    let fail_res = setup.close_vote_commitment_account(name, title, council_vote);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.close_council_vote(name, title, None);
+    assert!(success_res.is_ok());
+
+    // A community quorum out of reach of the council votes
+    let title = "Council only";
+    let end = Utc::now().timestamp() + 1;
+    let _ = setup.update_governance_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            quorum: Quorum::Absolute(100),
+            council_chamber: Some(CouncilMembership::Members),
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.create_proposal_in_chamber(name, title, &options, Chamber::Council);
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_council_vote(name, title, choice, salt);
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_council_vote(name, title, choice, salt);
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Succeeded);
+}
+
+#[test]
+fn test_council_mint() {
+    let setup = TestSetup::new();
+    let name = "superteam_council_mint";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    let council_mint = Keypair::new();
+    let source = get_associated_token_address(&setup.payer.pubkey(), &council_mint.pubkey());
+    let _ = setup.create_mint(&council_mint);
+    let _ = setup.create_associated_token_account(&setup.payer.pubkey(), &council_mint.pubkey());
+    let _ = setup.mint_to(&council_mint.pubkey(), &source, 100);
+
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            council_chamber: Some(CouncilMembership::Mint(council_mint.pubkey())),
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+
+    let options = ["Yes", "No"];
+    let _ = setup.create_proposal_in_chamber(name, title, &options, Chamber::Council);
+
+    // Fail pattern (Council mint missing)
+    let fail_res = setup.start_vote(name, title, end);
+    assert!(fail_res.is_err());
+
+    let _ = setup.start_vote_with_council_mint(name, title, end, Some(council_mint.pubkey()));
+
+    // Fail pattern (Community vote on a council proposal)
+    let fail_res = setup.commit_vote(name, title, choice, salt);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.commit_council_vote_with_tokens(
+        name,
+        title,
+        choice,
+        salt,
+        &council_mint.pubkey(),
+        &source,
+        60,
+    );
+    assert!(success_res.is_ok());
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_council_vote(name, title, choice, salt);
+
+    // Fail pattern (Not finalized)
+    let fail_res = setup.close_council_vote(name, title, Some(source));
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.council_tallies, vec![60, 0]);
+    assert_eq!(proposal.council_max_vote_weight, 100);
+    assert_eq!(proposal.state, ProposalState::Succeeded);
+
+    // Fail pattern (Escrow missing)
+    let fail_res = setup.close_council_vote(name, title, None);
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.close_council_vote(name, title, Some(source));
+    assert!(success_res.is_ok());
+
+    let balance = setup
+        .program
+        .rpc()
+        .get_token_account_balance(&source)
+        .unwrap()
+        .amount;
+    assert_eq!(balance, "100");
+}
+
+#[test]
+fn test_chamber_outcome() {
+    assert_eq!(Chamber::Community.decide(Some(0), None), Some(0));
+    assert_eq!(Chamber::Council.decide(Some(0), Some(1)), Some(1));
+
+    // Both chambers must pass the same option
+    assert_eq!(Chamber::Both.decide(Some(1), Some(1)), Some(1));
+    assert_eq!(Chamber::Both.decide(Some(0), Some(1)), None);
+    assert_eq!(Chamber::Both.decide(Some(0), None), None);
+    assert_eq!(Chamber::Both.decide(None, Some(0)), None);
+
+    // Each chamber is held to its own quorum
+    let config = GovernanceConfig {
+        quorum: Quorum::Absolute(100),
+        ..GovernanceConfig::default()
+    };
+    assert_eq!(config.winning_option(3, &[1, 0], 0), None);
+    assert_eq!(config.council_winning_option(3, &[1, 0], 0), Some(0));
+}
+
+#[test]
+fn test_config_change() {
+    let setup = TestSetup::new();
+    let name = "superteam_config_change";
+    let title = "Hello World";
+    let end = Utc::now().timestamp() + 1;
+    let choice = VoteChoice::Option(0); // Yes
+    let salt = "salt";
+
+    // Fail pattern (Council of members no one can list)
+    let fail_res = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            council_chamber: Some(CouncilMembership::Members),
+            allow_authority_updates: false,
+            ..GovernanceConfig::default()
+        },
+    );
+    assert!(fail_res.is_err());
+
+    // Authority updates disabled once the council is listed
+    let council_name = "superteam_config_change_council";
+    let council_config = GovernanceConfig {
+        council_chamber: Some(CouncilMembership::Members),
+        ..GovernanceConfig::default()
+    };
+    let _ = setup.create_governance_with_config(council_name, council_config.clone());
+    let disabled_config = GovernanceConfig {
+        allow_authority_updates: false,
+        ..council_config
+    };
+
+    // Fail pattern (Council of members not listed yet)
+    let fail_res = setup.update_governance_config(council_name, disabled_config.clone());
+    assert!(fail_res.is_err());
+
+    let _ = setup.create_council(council_name, vec![setup.payer.pubkey()]);
+
+    // Success pattern
+    let success_res = setup.update_governance_config(council_name, disabled_config);
+    assert!(success_res.is_ok());
+
+    let council_mint = Pubkey::new_unique();
+    let _ = setup.create_governance_with_config(
+        name,
+        GovernanceConfig {
+            reveal_period: 2,
+            council_chamber: Some(CouncilMembership::Mint(council_mint)),
+            allow_authority_updates: false,
+            ..GovernanceConfig::default()
+        },
+    );
+    let _ = setup.join(name);
+
+    let config = GovernanceConfig {
+        reveal_period: 2,
+        quorum: Quorum::Absolute(2),
+        council_chamber: Some(CouncilMembership::Mint(council_mint)),
+        allow_authority_updates: false,
+        ..GovernanceConfig::default()
+    };
+
+    // Fail pattern (Authority updates disabled)
+    let fail_res = setup.update_governance_config(name, config.clone());
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Council only proposal)
+    let council_title = "Council only";
+    let options = ["Yes", "No"];
+    let _ = setup.create_proposal_in_chamber(name, council_title, &options, Chamber::Council);
+    let fail_res = setup.insert_config_change(name, council_title, 0, config.clone());
+    assert!(fail_res.is_err());
+
+    let _ = setup.create_proposal(name, title);
+
+    // Fail pattern (Council of members no one can list)
+    let fail_res = setup.insert_config_change(
+        name,
+        title,
+        0,
+        GovernanceConfig {
+            council_chamber: Some(CouncilMembership::Members),
+            ..config.clone()
+        },
+    );
+    assert!(fail_res.is_err());
+
+    // Fail pattern (Invalid config)
+    let fail_res = setup.insert_config_change(
+        name,
+        title,
+        0,
+        GovernanceConfig {
+            reveal_period: 0,
+            ..config.clone()
+        },
+    );
+    assert!(fail_res.is_err());
+
+    // Success pattern
+    let success_res = setup.insert_config_change(name, title, 0, config.clone());
+    assert!(success_res.is_ok());
+
+    let _ = setup.start_vote(name, title, end);
+    let _ = setup.commit_vote(name, title, choice, salt);
+
+    // Fail pattern (Not succeeded)
+    let fail_res = setup.execute_config_change(name, title);
+    assert!(fail_res.is_err());
+
+    sleep(std::time::Duration::new(1, 0));
+    let _ = setup.reveal_vote(name, title, choice, salt);
+    sleep(std::time::Duration::new(3, 0));
+    let _ = setup.finalize_proposal(name, title);
+
+    // Success pattern
+    let success_res = setup.execute_config_change(name, title);
+    assert!(success_res.is_ok());
+
+    let governance: Governance = setup
+        .program
+        .account(setup.get_governance_pda(name))
+        .unwrap();
+    assert_eq!(governance.config, config);
+
+    let proposal: Proposal = setup
+        .program
+        .account(setup.get_proposal_pda(name, title))
+        .unwrap();
+    assert_eq!(proposal.state, ProposalState::Executed);
+
+    // Fail pattern (Already executed)
+    let fail_res = setup.execute_config_change(name, title);
     assert!(fail_res.is_err());
 }